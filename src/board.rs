@@ -1,12 +1,17 @@
+use crate::clock::{Clock, SystemClock};
+use crate::error::MinesweeperError;
+use crate::render::CellRenderer;
 use crate::solver::Solver;
 
 use std::collections::{HashSet, VecDeque};
 use std::fmt;
+use std::iter::{once, zip};
+use std::ops::RangeInclusive;
+use std::sync::Arc;
 use std::time::Duration;
 
 use itertools::iproduct;
-use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::{thread_rng, Rng};
 
 use wasm_timer::Instant; //Should be behind a compile flag, else import time::Instant
 
@@ -17,6 +22,110 @@ pub enum GameState {
     Lost,
 }
 
+#[derive(Clone, PartialEq, Eq, Debug, Copy)]
+pub enum EndReason {
+    Won,
+    Lost,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Copy)]
+pub enum MoveKind {
+    Click,
+    Flag,
+}
+
+/// Which cells count as neighbours for numbers, cascades and chords.
+#[derive(Clone, PartialEq, Eq, Debug, Copy)]
+pub enum Adjacency {
+    Eight,
+    Orthogonal,
+    Diagonal,
+}
+
+/// How far opening a zero spreads. `Full` is the classic cascade through
+/// every connected zero, `SingleRing` opens only the neighbours of a zero
+/// the player opened directly (by click or chord), and `None` opens nothing
+/// beyond the cell itself.
+#[derive(Clone, PartialEq, Eq, Debug, Copy)]
+pub enum FloodMode {
+    Full,
+    SingleRing,
+    None,
+}
+
+/// The eight rotations and reflections of a rectangle. The quarter turns
+/// and the two transpositions only map a board onto itself when it is
+/// square.
+#[derive(Clone, PartialEq, Eq, Debug, Copy)]
+pub enum Symmetry {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipHorizontal,
+    FlipVertical,
+    Transpose,
+    AntiTranspose,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipHorizontal,
+        Symmetry::FlipVertical,
+        Symmetry::Transpose,
+        Symmetry::AntiTranspose,
+    ];
+
+    fn swaps_dimensions(self) -> bool {
+        matches!(
+            self,
+            Symmetry::Rotate90
+                | Symmetry::Rotate270
+                | Symmetry::Transpose
+                | Symmetry::AntiTranspose
+        )
+    }
+
+    /// The cell of a `rows`x`cols` board that ends up at `(x, y)`.
+    fn source(self, rows: usize, cols: usize, x: usize, y: usize) -> (usize, usize) {
+        match self {
+            Symmetry::Identity => (x, y),
+            Symmetry::Rotate90 => (rows - 1 - y, x),
+            Symmetry::Rotate180 => (rows - 1 - x, cols - 1 - y),
+            Symmetry::Rotate270 => (y, cols - 1 - x),
+            Symmetry::FlipHorizontal => (x, cols - 1 - y),
+            Symmetry::FlipVertical => (rows - 1 - x, y),
+            Symmetry::Transpose => (y, x),
+            Symmetry::AntiTranspose => (cols - 1 - y, rows - 1 - x),
+        }
+    }
+}
+
+/// Features of one frontier cell, as returned by `Board::training_samples`.
+///
+/// `neighbors` lists the 3x3 block around the cell row by row, skipping the
+/// cell itself: `(x-1, y-1), (x-1, y), (x-1, y+1), (x, y-1), (x, y+1),
+/// (x+1, y-1), (x+1, y), (x+1, y+1)`. Each entry holds the cell's
+/// `to_numeric_grid` code, or `None` when it lies off the board.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FrontierFeatures {
+    pub position: Position,
+    pub neighbors: [Option<i8>; 8],
+    pub flagged_neighbors: u8,
+    pub covered_neighbors: u8,
+}
+
+/// Board metrics gathered in one call by `Board::analyze`.
+#[derive(Clone, PartialEq, Eq, Debug, Copy)]
+pub struct BoardAnalysis {
+    pub three_bv: usize,
+    pub region_count: usize,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum BoardCellState {
     Discovered = 0,
@@ -27,6 +136,65 @@ pub enum BoardCellState {
     Other,
 }
 
+/// Wall patterns recognised by `Board::detect_patterns`. Both are read off a
+/// straight run of revealed numbers whose covered neighbours all lie in the
+/// parallel line on one side; values count flags as already satisfied.
+///
+/// - `OneTwoOne`: 1-2-1 has mines beside each 1 and is safe everywhere else
+///   along the side line, from one cell before the run to one cell past it.
+/// - `OneTwoTwoOne`: 1-2-2-1 has mines beside the two 2s and is safe
+///   everywhere else along the side line.
+#[derive(Clone, PartialEq, Eq, Debug, Copy)]
+pub enum Pattern {
+    OneTwoOne,
+    OneTwoTwoOne,
+}
+
+impl Pattern {
+    fn values(self) -> &'static [u8] {
+        match self {
+            Pattern::OneTwoOne => &[1, 2, 1],
+            Pattern::OneTwoTwoOne => &[1, 2, 2, 1],
+        }
+    }
+
+    /// Offsets along the side line, relative to the first number, that
+    /// hold mines.
+    fn mine_offsets(self) -> &'static [i32] {
+        match self {
+            Pattern::OneTwoOne => &[0, 2],
+            Pattern::OneTwoTwoOne => &[1, 2],
+        }
+    }
+}
+
+/// One occurrence of a `Pattern`: the numbers that form it and the covered
+/// cells it settles.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PatternMatch {
+    pub pattern: Pattern,
+    pub numbers: Vec<Position>,
+    pub mines: Vec<Position>,
+    pub safe: Vec<Position>,
+}
+
+/// What a player sees in a cell, independent of how `BoardCell` packs it.
+/// `WrongFlag` marks a flag on a safe cell once the game is lost.
+/// `CoveredFrontier` and `CoveredInterior` replace `Covered` in `view` when
+/// `set_split_covered_view` is enabled.
+#[derive(Clone, PartialEq, Eq, Debug, Copy)]
+pub enum CellView {
+    Covered,
+    CoveredFrontier,
+    CoveredInterior,
+    Flagged,
+    Question,
+    Revealed(u8),
+    Mine,
+    Exploded,
+    WrongFlag,
+}
+
 #[derive(Clone, Eq)]
 pub struct BoardCell {
     cell: u8,
@@ -71,6 +239,9 @@ impl BoardCell {
             Self::from_raw_parts(0, BoardCellState::Other)
         }
     }
+    pub fn from_byte(byte: u8) -> Self {
+        Self { cell: byte }
+    }
     fn from_raw_parts(value: u8, state: BoardCellState) -> Self {
         Self {
             cell: ((state as u8) << 4) + value,
@@ -92,6 +263,15 @@ impl BoardCell {
     pub fn value(&self) -> u8 {
         self.cell & ((1 << 4) - 1)
     }
+    pub fn is_mine(&self) -> bool {
+        self.value() == 15
+    }
+    pub fn is_covered(&self) -> bool {
+        matches!(
+            self.state(),
+            BoardCellState::Blank | BoardCellState::Flagged | BoardCellState::Question
+        )
+    }
     pub fn click(&mut self) -> bool {
         if self.state() == BoardCellState::Blank {
             self.cell = self.value();
@@ -102,8 +282,11 @@ impl BoardCell {
         false
     }
 
+    /// Cycles a covered cell through Blank -> Flagged -> Question -> Blank
+    /// and returns the change in the number of `Flagged` cells. Revealed
+    /// and exploded cells are left untouched.
     pub fn flag(&mut self) -> i8 {
-        if self.state() != BoardCellState::Discovered {
+        if self.is_covered() {
             self.cell = self.value() + (((self.state() as u8) % 3 + 1) << 4);
         }
         match self.state() {
@@ -120,6 +303,88 @@ impl Default for BoardCell {
     }
 }
 pub type BoardCells = Vec<Vec<BoardCell>>;
+
+/// Coordinate type used by the public `Board` API. Enabling the
+/// `u16-coords` feature halves the size of stored coordinates.
+#[cfg(not(feature = "u16-coords"))]
+pub type Coord = usize;
+#[cfg(feature = "u16-coords")]
+pub type Coord = u16;
+
+pub type Position = (Coord, Coord);
+
+#[allow(clippy::unnecessary_cast)]
+fn idx(c: Coord) -> usize {
+    c as usize
+}
+
+/// Whether every index below `len` can be written as a `Coord`.
+#[allow(clippy::unnecessary_fallible_conversions)]
+fn fits_coord(len: usize) -> bool {
+    Coord::try_from(len - 1).is_ok()
+}
+
+/// Picks `amount` distinct values from `0..len` with a partial Fisher-Yates
+/// shuffle. Only `Rng::gen_range` over `u64` is used, so a seeded RNG gives
+/// the same picks regardless of `rand`'s sampling internals or the target's
+/// pointer width.
+fn choose_positions(rng: &mut impl Rng, len: usize, amount: usize) -> Vec<usize> {
+    let mut positions: Vec<usize> = (0..len).collect();
+    let amount = amount.min(len);
+    for i in 0..amount {
+        let j = rng.gen_range(i as u64..len as u64) as usize;
+        positions.swap(i, j);
+    }
+    positions.truncate(amount);
+    positions
+}
+
+fn remap<T: Clone>(
+    grid: &[Vec<T>],
+    rows: usize,
+    cols: usize,
+    source: &impl Fn(usize, usize) -> (usize, usize),
+) -> Vec<Vec<T>> {
+    (0..rows)
+        .map(|x| {
+            (0..cols)
+                .map(|y| {
+                    let (sx, sy) = source(x, y);
+                    grid[sx][sy].clone()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Whether the player's flag on a cell has let a chord open something, for
+/// `Board::flag_efficiency`.
+#[derive(Clone, PartialEq, Eq, Debug, Copy)]
+enum FlagUse {
+    None,
+    Placed,
+    UsedByChord,
+}
+
+/// Everything the player has changed on a board, without the layout, as
+/// saved by `Board::save_play_state`. Per-cell data is kept in flat
+/// row-major vectors.
+#[derive(Clone, Debug)]
+pub struct PlayState {
+    states: Vec<u8>,
+    game_state: GameState,
+    clicked_cells: usize,
+    flagged_cells: i16,
+    start_time: Option<Instant>,
+    display_time: Duration,
+    last_action_time: Option<Instant>,
+    player_flagged_all_mines: bool,
+    reveal_order: Vec<Option<u32>>,
+    flag_use: Vec<FlagUse>,
+    reveal_counter: u32,
+    transition_consumed: bool,
+}
+
 #[derive(Debug)]
 pub struct Board {
     board: BoardCells,
@@ -133,6 +398,23 @@ pub struct Board {
     start_time: Option<Instant>, //Check Instant is behind compile flag for correctness.
     display_time: Duration,
     solver: Option<Solver>,
+    click_unflag: bool,
+    player_flagged_all_mines: bool,
+    reveal_order: Vec<Vec<Option<u32>>>,
+    flag_use: Vec<Vec<FlagUse>>,
+    reveal_counter: u32,
+    transition_consumed: bool,
+    max_reveal_per_click: Option<usize>,
+    split_covered_view: bool,
+    mine_mobility: bool,
+    auto_complete: bool,
+    flood_mode: FloodMode,
+    adjacency: Adjacency,
+    reveal_queue: VecDeque<(Position, u32)>,
+    reveal_visited: Vec<u64>,
+    reveal_waves: Vec<usize>,
+    last_action_time: Option<Instant>,
+    clock: Arc<dyn Clock>,
 }
 
 impl Board {
@@ -151,13 +433,272 @@ impl Board {
             start_time: None,
             display_time: Duration::ZERO,
             solver: None,
+            click_unflag: false,
+            player_flagged_all_mines: false,
+            reveal_order: vec![vec![None; cols]; rows],
+            flag_use: vec![vec![FlagUse::None; cols]; rows],
+            reveal_counter: 0,
+            transition_consumed: false,
+            max_reveal_per_click: None,
+            split_covered_view: false,
+            mine_mobility: false,
+            auto_complete: false,
+            flood_mode: FloodMode::Full,
+            adjacency: Adjacency::Eight,
+            reveal_queue: VecDeque::new(),
+            reveal_visited: vec![0; (rows * cols).div_ceil(64)],
+            reveal_waves: Vec::new(),
+            last_action_time: None,
+            clock: Arc::new(SystemClock),
         }
     }
 
-    pub fn start(&mut self, x: usize, y: usize, flag: bool) {
+    /// Checked version of `new`, see `BoardBuilder::build` for the rules.
+    pub fn try_new(rows: usize, cols: usize, mines: usize) -> Result<Self, MinesweeperError> {
+        BoardBuilder::new()
+            .rows(rows)
+            .cols(cols)
+            .mines(mines)
+            .build()
+    }
+
+    pub fn builder() -> BoardBuilder {
+        BoardBuilder::new()
+    }
+
+    pub fn neighbors(&self, x: Coord, y: Coord) -> impl Iterator<Item = Position> {
+        let (x, y) = (idx(x) as i32, idx(y) as i32);
+        let (rows, cols) = (self.rows as i32, self.cols as i32);
+        let adjacency = self.adjacency;
+        iproduct!(-1..=1, -1..=1)
+            .filter(move |&(dx, dy)| match adjacency {
+                Adjacency::Eight => dx != 0 || dy != 0,
+                Adjacency::Orthogonal => (dx == 0) != (dy == 0),
+                Adjacency::Diagonal => dx != 0 && dy != 0,
+            })
+            .map(move |(dx, dy)| (x + dx, y + dy))
+            .filter(move |&(x1, y1)| 0 <= x1 && x1 < rows && 0 <= y1 && y1 < cols)
+            .map(|(x1, y1)| (x1 as Coord, y1 as Coord))
+    }
+
+    /// The eight neighbours of `(x, y)` as the player sees them, in the fixed
+    /// order N, NE, E, SE, S, SW, W, NW (north is `x - 1`, east is `y + 1`),
+    /// with `None` for positions off the board.
+    pub fn neighbor_states(&self, x: Coord, y: Coord) -> [Option<CellView>; 8] {
+        let (x, y) = (idx(x) as i32, idx(y) as i32);
+        COMPASS.map(|(dx, dy)| {
+            let (x1, y1) = (x + dx, y + dy);
+            (0 <= x1 && x1 < self.rows as i32 && 0 <= y1 && y1 < self.cols as i32)
+                .then(|| self.cell_view(&self.board[x1 as usize][y1 as usize]))
+        })
+    }
+
+    /// Number of mines around `(x, y)`, regardless of reveal state. Unlike
+    /// `value()`, this is also the real count for mine cells.
+    pub fn adjacent_mine_count(&self, x: Coord, y: Coord) -> u8 {
+        self.neighbors(x, y)
+            .filter(|&(x1, y1)| self.cell(x1, y1).is_mine())
+            .count() as u8
+    }
+
+    /// Overwrites the cell at `(x, y)` for building test fixtures. This
+    /// bypasses the board's invariants: neighbouring numbers and `mines`
+    /// are not updated, and the board is marked as started so the next
+    /// click doesn't lay mines over the fixture. Only the clicked and
+    /// flagged counters are adjusted for the replaced cell.
+    #[cfg(feature = "testing")]
+    pub fn set_cell(&mut self, x: Coord, y: Coord, cell: BoardCell) {
+        let opened = |cell: &BoardCell| {
+            (cell.state() == BoardCellState::Discovered && !cell.is_mine()) as usize
+        };
+        let flagged = |cell: &BoardCell| (cell.state() == BoardCellState::Flagged) as i16;
+        let old = std::mem::replace(self.cell_mut(x, y), cell);
+        // Saturating, as a fixture can open cells without counting them.
+        self.clicked_cells =
+            (self.clicked_cells + opened(self.cell(x, y))).saturating_sub(opened(&old));
+        self.flagged_cells += flagged(self.cell(x, y)) - flagged(&old);
+        self.start = true;
+    }
+
+    fn cell(&self, x: Coord, y: Coord) -> &BoardCell {
+        &self.board[idx(x)][idx(y)]
+    }
+
+    fn cell_mut(&mut self, x: Coord, y: Coord) -> &mut BoardCell {
+        &mut self.board[idx(x)][idx(y)]
+    }
+
+    pub fn start(&mut self, x: Coord, y: Coord, flag: bool) {
         //populate board
         log::debug!("Fill Board");
-        let mut rng = thread_rng();
+        // With no mines every cell stays a zero, so the first click's
+        // cascade opens the whole board and wins immediately.
+        if self.mines > 0 {
+            self.place_mines(idx(x), idx(y), flag);
+        }
+        log::debug!("Finish Board Filling");
+        self.begin();
+    }
+
+    /// Like `start`, but every mine goes in `region`, outside the 3x3 block
+    /// around `(x, y)` that a first click keeps clear. Numbers still count
+    /// mines across the whole board. Fails if `(x, y)` or a region cell is
+    /// off the board or fewer than `mines` region cells are available; does
+    /// nothing once the game has started. The click itself is left to the caller.
+    pub fn start_in_region(
+        &mut self,
+        x: Coord,
+        y: Coord,
+        region: &[Position],
+    ) -> Result<(), MinesweeperError> {
+        if self.start {
+            return Ok(());
+        }
+        if idx(x) >= self.rows || idx(y) >= self.cols {
+            return Err(MinesweeperError::OutOfBounds {
+                x: idx(x),
+                y: idx(y),
+            });
+        }
+        if let Some(&(rx, ry)) = region
+            .iter()
+            .find(|&&(rx, ry)| idx(rx) >= self.rows || idx(ry) >= self.cols)
+        {
+            return Err(MinesweeperError::OutOfBounds {
+                x: idx(rx),
+                y: idx(ry),
+            });
+        }
+        let mut allowed: Vec<(usize, usize)> = region
+            .iter()
+            .map(|&(rx, ry)| (idx(rx), idx(ry)))
+            .filter(|&(rx, ry)| rx.abs_diff(idx(x)) > 1 || ry.abs_diff(idx(y)) > 1)
+            .collect();
+        allowed.sort_unstable();
+        allowed.dedup();
+        if allowed.len() < self.mines {
+            return Err(MinesweeperError::TooManyMines {
+                mines: self.mines,
+                max: allowed.len(),
+            });
+        }
+        let chosen = choose_positions(&mut thread_rng(), allowed.len(), self.mines);
+        self.lay_mines(chosen.into_iter().map(|i| allowed[i]));
+        self.begin();
+        Ok(())
+    }
+
+    fn begin(&mut self) {
+        self.solver = Solver::from_board(&self.board).into();
+        self.solver.as_mut().unwrap().start();
+        self.start = true;
+        self.start_time = Some(self.clock.now());
+    }
+
+    /// Saves the reveal and flag state of every cell plus the counters and
+    /// timers, leaving out the mine layout and the solver. A save is three
+    /// flat allocations of `rows * cols` entries, about ten bytes a cell,
+    /// and restoring copies them back in place without allocating. Much
+    /// cheaper than cloning the board, for trying many continuations from
+    /// one position.
+    pub fn save_play_state(&self) -> PlayState {
+        PlayState {
+            states: self
+                .board
+                .iter()
+                .flatten()
+                .map(|cell| cell.cell >> 4)
+                .collect(),
+            game_state: self.game_state,
+            clicked_cells: self.clicked_cells,
+            flagged_cells: self.flagged_cells,
+            start_time: self.start_time,
+            display_time: self.display_time,
+            last_action_time: self.last_action_time,
+            player_flagged_all_mines: self.player_flagged_all_mines,
+            reveal_order: self.reveal_order.iter().flatten().copied().collect(),
+            flag_use: self.flag_use.iter().flatten().copied().collect(),
+            reveal_counter: self.reveal_counter,
+            transition_consumed: self.transition_consumed,
+        }
+    }
+
+    /// Puts back a state saved by `save_play_state` on this board. Any
+    /// cascade still pending from `max_reveal_per_click` is dropped.
+    pub fn restore_play_state(&mut self, state: PlayState) {
+        debug_assert_eq!(state.states.len(), self.rows * self.cols);
+        for (cell, saved) in zip(self.board.iter_mut().flatten(), state.states) {
+            cell.cell = (saved << 4) + cell.value();
+        }
+        self.game_state = state.game_state;
+        self.clicked_cells = state.clicked_cells;
+        self.flagged_cells = state.flagged_cells;
+        self.start_time = state.start_time;
+        self.display_time = state.display_time;
+        self.last_action_time = state.last_action_time;
+        self.player_flagged_all_mines = state.player_flagged_all_mines;
+        for (order, saved) in zip(self.reveal_order.iter_mut().flatten(), state.reveal_order) {
+            *order = saved;
+        }
+        for (used, saved) in zip(self.flag_use.iter_mut().flatten(), state.flag_use) {
+            *used = saved;
+        }
+        self.reveal_counter = state.reveal_counter;
+        self.transition_consumed = state.transition_consumed;
+        self.reveal_queue.clear();
+        self.reveal_visited.fill(0);
+        self.reveal_waves.clear();
+    }
+
+    /// Replaces the board's time source, e.g. with a `ManualClock` shared by
+    /// several boards so they can be paused together.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Starts the same game over: every cell is covered again and all play
+    /// state is cleared, while the mine layout and numbers (stored in each
+    /// cell's value bits) are kept. The timer restarts immediately.
+    pub fn restart_same(&mut self) {
+        if !self.start {
+            return;
+        }
+        for cell in self.board.iter_mut().flatten() {
+            *cell = BoardCell::from_raw_parts(cell.value(), BoardCellState::Blank);
+        }
+        self.game_state = GameState::InProgress;
+        self.clicked_cells = 0;
+        self.flagged_cells = 0;
+        self.start_time = Some(self.clock.now());
+        self.display_time = Duration::ZERO;
+        self.player_flagged_all_mines = false;
+        self.reveal_order = vec![vec![None; self.cols]; self.rows];
+        self.flag_use = vec![vec![FlagUse::None; self.cols]; self.rows];
+        self.reveal_counter = 0;
+        self.transition_consumed = false;
+        self.reveal_queue.clear();
+        self.reveal_visited.fill(0);
+        self.reveal_waves.clear();
+        self.last_action_time = None;
+        self.solver = Solver::from_board(&self.board).into();
+        self.solver.as_mut().unwrap().start();
+    }
+
+    /// The usual opening move used by tests: generate the board around
+    /// `(0, 0)` and click it.
+    pub fn start_at_origin(&mut self) {
+        if !self.start {
+            self.start(0, 0, true);
+        }
+        self.click(0, 0);
+    }
+
+    fn place_mines(&mut self, x: usize, y: usize, flag: bool) {
+        self.place_mines_with(&mut thread_rng(), x, y, flag);
+    }
+
+    fn place_mines_with(&mut self, rng: &mut impl Rng, x: usize, y: usize, flag: bool) {
         let _place = x * self.cols + y;
         log::debug!("Create Mines");
         let mut places = iproduct!(-1..=1, -1..=1)
@@ -187,11 +728,8 @@ impl Board {
             temp
         };
         //log::info!("{:?}", places);
-        let mut pos = (0..(self.rows * self.cols - places.iter().fold(0, |acc, (_, x)| acc + x))) //Counting is hard
-            .collect::<Vec<usize>>()
-            .choose_multiple(&mut rng, self.mines)
-            .copied()
-            .collect::<Vec<usize>>();
+        let free = self.rows * self.cols - places.iter().fold(0, |acc, (_, x)| acc + x); //Counting is hard
+        let mut pos = choose_positions(rng, free, self.mines);
         pos.sort_unstable();
         let mut delta = 0;
         let mut i = 0;
@@ -213,155 +751,3288 @@ impl Board {
         //log::info!("self.m:{}", self.m);
         //log::info!("pos:{:?}", pos);
         log::debug!("Place Mines");
-        for (x, y) in pos {
+        self.lay_mines(pos);
+    }
+
+    /// Puts a mine on each of `positions` and bumps the numbers around it.
+    fn lay_mines(&mut self, positions: impl IntoIterator<Item = (usize, usize)>) {
+        for (x, y) in positions {
             self.board[x][y].cell = 15 + ((self.board[x][y].state() as u8) << 4);
-            for (dx, dy) in iproduct!(-1..=1, -1..=1) {
-                let x1 = x as i32 + dx;
-                let y1 = y as i32 + dy;
-                if 0 <= x1 && x1 < self.rows as i32 && 0 <= y1 && y1 < self.cols as i32 {
-                    let x1 = x1 as usize;
-                    let y1 = y1 as usize;
-                    if self.board[x1][y1].value() != 15 {
-                        self.board[x1][y1].cell += 1;
-                        //log::info!("({},{}): {}", x1, y1, self.board[x1][y1].flags(),);
-                    }
+            for (x1, y1) in self.neighbors(x as Coord, y as Coord) {
+                let cell = self.cell_mut(x1, y1);
+                if cell.value() != 15 {
+                    cell.cell += 1;
                 }
             }
         }
-        log::debug!("Finish Board Filling");
-
-        self.solver = Solver::from_board(&self.board).into();
-        self.solver.as_mut().unwrap().start();
-        self.start = true;
-        self.start_time = Some(Instant::now());
     }
 
-    pub fn flag(&mut self, x: usize, y: usize) {
-        if self.board[x][y].state() == BoardCellState::Discovered {
+    pub fn flag(&mut self, x: Coord, y: Coord) {
+        self.last_action_time = Some(self.clock.now());
+        if self.cell(x, y).state() == BoardCellState::Discovered {
             self.click(x, y);
         }
         if !self.start {
             self.start(x, y, false);
         }
-        self.flagged_cells += self.board[x][y].flag() as i16;
+        self.flagged_cells += self.cell_mut(x, y).flag() as i16;
+        self.flag_use[idx(x)][idx(y)] = if self.cell(x, y).state() == BoardCellState::Flagged {
+            FlagUse::Placed
+        } else {
+            FlagUse::None
+        };
+        self.run_auto_complete();
     }
 
-    pub fn click(&mut self, x: usize, y: usize) {
+    pub fn click(&mut self, x: Coord, y: Coord) {
         log::debug!("Clicked");
+        self.last_action_time = Some(self.clock.now());
         if !self.start {
             self.start(x, y, true);
         }
-        let mut q = VecDeque::new();
-        let mut set = HashSet::new();
+        if self.click_unflag && self.cell(x, y).state() == BoardCellState::Flagged {
+            let value = self.cell(x, y).value();
+            *self.cell_mut(x, y) = BoardCell::from_raw_parts(value, BoardCellState::Blank);
+            self.flagged_cells -= 1;
+            self.flag_use[idx(x)][idx(y)] = FlagUse::None;
+            self.run_auto_complete();
+            return;
+        }
         log::debug!("Check if flagged");
-        if self.board[x][y].state() == BoardCellState::Discovered {
-            let mut count = 0;
-            for (dx, dy) in iproduct!(-1..=1, -1..=1) {
-                let x1 = x as i32 + dx;
-                let y1 = y as i32 + dy;
-                if 0 <= x1 && x1 < self.rows as i32 && 0 <= y1 && y1 < self.cols as i32 {
-                    let x1 = x1 as usize;
-                    let y1 = y1 as usize;
-                    if self.board[x1][y1].state() == BoardCellState::Flagged {
-                        count += 1;
-                    }
+        if self.cell(x, y).state() == BoardCellState::Discovered
+            && self.flagged_neighbors(x, y) == self.cell(x, y).value() as usize
+        {
+            let mut opened = false;
+            for (x1, y1) in self.neighbors(x, y) {
+                if self.cell(x1, y1).state() == BoardCellState::Blank {
+                    self.enqueue_reveal(x1, y1, 0);
+                    opened = true;
                 }
             }
-            if count == self.board[x][y].value() {
-                for (dx, dy) in iproduct!(-1..=1, -1..=1) {
-                    let x1 = x as i32 + dx;
-                    let y1 = y as i32 + dy;
-                    if 0 <= x1 && x1 < self.rows as i32 && 0 <= y1 && y1 < self.cols as i32 {
-                        let x1 = x1 as usize;
-                        let y1 = y1 as usize;
-                        if self.board[x1][y1].state() == BoardCellState::Blank {
-                            q.push_back((x1, y1));
-                            set.insert((x1, y1));
-                        }
+            if opened {
+                for (x1, y1) in self.neighbors(x, y) {
+                    let used = &mut self.flag_use[idx(x1)][idx(y1)];
+                    if *used == FlagUse::Placed {
+                        *used = FlagUse::UsedByChord;
                     }
                 }
             }
         }
         log::debug!("Check if clickable");
-        if self.board[x][y].state() == BoardCellState::Blank {
-            q.push_back((x, y));
-            set.insert((x, y));
+        self.reveal_waves.clear();
+        let mut revealed = Vec::new();
+        // The clicked cell opens at once, ahead of any capped cascade still
+        // pending; only the flood is held to `max_reveal_per_click`.
+        if self.cell(x, y).state() == BoardCellState::Blank
+            && !self.open_cell(x, y, 0, &mut revealed)
+        {
+            return;
         }
         log::debug!("Check all discovered values");
-        //Maybe optimize in future
-        while let Some((x, y)) = q.pop_front() {
-            //BFS
-            if self.board[x][y].value() == 15 {
-                self.board[x][y].click();
-                self.game_state = GameState::Lost;
-                self.board[x][y].cell = 15 + (4 << 4);
-                return;
-            }
-            if self.board[x][y].state() == BoardCellState::Blank {
-                self.clicked_cells += 1;
-            }
-            if self.board[x][y].click() {
-                for (dx, dy) in iproduct!(-1..=1, -1..=1) {
-                    let x1 = x as i32 + dx;
-                    let y1 = y as i32 + dy;
-                    if 0 <= x1 && x1 < self.rows as i32 && 0 <= y1 && y1 < self.cols as i32 {
-                        let x1 = x1 as usize;
-                        let y1 = y1 as usize;
-                        if self.board[x1][y1].state() == BoardCellState::Blank
-                            && !set.contains(&(x1, y1))
-                        {
-                            q.push_back((x1, y1));
-                            set.insert((x1, y1));
-                        }
-                    }
+        revealed.extend(self.reveal());
+        if self.game_state == GameState::Lost {
+            return;
+        }
+        self.check_won();
+        if self.mine_mobility && self.game_state == GameState::InProgress && !revealed.is_empty() {
+            self.relocate_mine(&revealed);
+        }
+        self.run_auto_complete();
+        log::debug!("Finish Click");
+    }
+
+    /// Casual assist: after every click or flag, any number whose flags
+    /// match its value opens its remaining neighbours, and so on through
+    /// whatever that opens (see `resolve_satisfied`). Wrong flags can make
+    /// this hit a mine and lose. Off by default.
+    pub fn set_auto_complete(&mut self, enabled: bool) {
+        self.auto_complete = enabled;
+    }
+
+    fn run_auto_complete(&mut self) {
+        if self.auto_complete && self.game_state == GameState::InProgress {
+            // resolve_satisfied goes through click; keep it from recursing.
+            self.auto_complete = false;
+            self.resolve_satisfied();
+            self.auto_complete = true;
+        }
+    }
+
+    /// Enables the moving-mines variant: after every click that opens a safe
+    /// cell, one unflagged mine moves to a random covered cell away from the
+    /// cells just opened. Off by default.
+    pub fn set_mine_mobility(&mut self, enabled: bool) {
+        self.mine_mobility = enabled;
+    }
+
+    /// Moves a random unflagged, covered mine to a random covered cell that
+    /// is neither a mine, next to any cell in `revealed`, nor waiting in a
+    /// capped reveal's queue, and fixes up the numbers around both. Does
+    /// nothing if either side has no candidate.
+    fn relocate_mine(&mut self, revealed: &[Position]) {
+        let unflagged = |cell: &BoardCell| {
+            matches!(
+                cell.state(),
+                BoardCellState::Blank | BoardCellState::Question
+            )
+        };
+        let near_revealed = |x: usize, y: usize| {
+            revealed
+                .iter()
+                .any(|&(x1, y1)| idx(x1).abs_diff(x) <= 1 && idx(y1).abs_diff(y) <= 1)
+        };
+        let pending = |x: usize, y: usize| {
+            let i = x * self.cols + y;
+            self.reveal_visited[i / 64] & (1 << (i % 64)) != 0
+        };
+        let cells = || iproduct!(0..self.rows, 0..self.cols);
+        let sources: Vec<_> = cells()
+            .filter(|&(x, y)| self.board[x][y].is_mine() && unflagged(&self.board[x][y]))
+            .collect();
+        let targets: Vec<_> = cells()
+            .filter(|&(x, y)| {
+                !self.board[x][y].is_mine()
+                    && unflagged(&self.board[x][y])
+                    && !near_revealed(x, y)
+                    && !pending(x, y)
+            })
+            .collect();
+        if sources.is_empty() || targets.is_empty() {
+            return;
+        }
+        let mut rng = thread_rng();
+        let (from, to) = (
+            sources[rng.gen_range(0..sources.len())],
+            targets[rng.gen_range(0..targets.len())],
+        );
+        let (from, to) = (
+            (from.0 as Coord, from.1 as Coord),
+            (to.0 as Coord, to.1 as Coord),
+        );
+        for (x1, y1) in self.neighbors(from.0, from.1) {
+            if !self.cell(x1, y1).is_mine() {
+                self.cell_mut(x1, y1).cell -= 1;
+            }
+        }
+        let state = self.cell(to.0, to.1).state();
+        *self.cell_mut(to.0, to.1) = BoardCell::from_raw_parts(15, state);
+        for (x1, y1) in self.neighbors(to.0, to.1) {
+            if !self.cell(x1, y1).is_mine() {
+                self.cell_mut(x1, y1).cell += 1;
+            }
+        }
+        let (value, state) = (
+            self.adjacent_mine_count(from.0, from.1),
+            self.cell(from.0, from.1).state(),
+        );
+        *self.cell_mut(from.0, from.1) = BoardCell::from_raw_parts(value, state);
+    }
+
+    /// Applies a newline-separated list of moves in order. Each line is
+    /// `C x y` to click or `F x y` to flag, with the parts separated by
+    /// whitespace and `x`, `y` as decimal coordinates; blank lines are
+    /// skipped. Stops quietly once the game ends. A malformed line or a
+    /// coordinate off the board returns an error, leaving the moves before
+    /// it applied.
+    pub fn apply_action_log(&mut self, log: &str) -> Result<(), MinesweeperError> {
+        for (n, line) in log.lines().enumerate() {
+            if self.start && self.game_state != GameState::InProgress {
+                break;
+            }
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.is_empty() {
+                continue;
+            }
+            let malformed = || MinesweeperError::ParseError(format!("line {}: {line:?}", n + 1));
+            let [action, x, y] = parts[..] else {
+                return Err(malformed());
+            };
+            let (Ok(x), Ok(y)) = (x.parse::<usize>(), y.parse::<usize>()) else {
+                return Err(malformed());
+            };
+            if x >= self.rows || y >= self.cols {
+                return Err(MinesweeperError::OutOfBounds { x, y });
+            }
+            match action {
+                "C" => self.click(x as Coord, y as Coord),
+                "F" => self.flag(x as Coord, y as Coord),
+                _ => return Err(malformed()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Chords every discovered number whose flags already match its value,
+    /// repeating until no more cells open, and returns how many opened. A
+    /// wrong flag can make a chord hit a mine, which loses as usual.
+    pub fn resolve_satisfied(&mut self) -> usize {
+        let before = self.clicked_cells;
+        let mut progress = true;
+        while progress && self.game_state == GameState::InProgress {
+            progress = false;
+            for (x, y) in iproduct!(0..self.rows, 0..self.cols) {
+                let (x, y) = (x as Coord, y as Coord);
+                let cell = self.cell(x, y);
+                if self.game_state == GameState::InProgress
+                    && cell.state() == BoardCellState::Discovered
+                    && !cell.is_mine()
+                    && self.flagged_neighbors(x, y) == cell.value() as usize
+                    && self
+                        .neighbors(x, y)
+                        .any(|(x1, y1)| self.cell(x1, y1).state() == BoardCellState::Blank)
+                {
+                    self.click(x, y);
+                    progress = true;
                 }
             }
         }
-        log::debug!("Check if game is won");
-        if self.clicked_cells + self.mines == self.cols * self.rows {
-            self.game_state = GameState::Won;
+        self.clicked_cells - before
+    }
+
+    /// Share of the player's flags on mines that a chord later relied on to
+    /// open cells. Flags never chorded against were not needed to clear the
+    /// board, so flagging every mine but never chording scores 0.0 and
+    /// flagging only what chords need scores 1.0. Flags removed again and
+    /// the ones placed at the end of a won game don't count. 1.0 when the
+    /// player has flagged no mines.
+    pub fn flag_efficiency(&self) -> f64 {
+        let (mut flagged, mut used) = (0, 0);
+        for (x, y) in iproduct!(0..self.rows, 0..self.cols) {
+            if !self.board[x][y].is_mine() {
+                continue;
+            }
+            match self.flag_use[x][y] {
+                FlagUse::None => (),
+                FlagUse::Placed => flagged += 1,
+                FlagUse::UsedByChord => {
+                    flagged += 1;
+                    used += 1;
+                }
+            }
+        }
+        if flagged == 0 {
+            1.0
+        } else {
+            used as f64 / flagged as f64
         }
-        log::debug!("Finish Click");
     }
 
-    pub fn get_display_time(&self) -> Duration {
-        match self.game_state {
-            GameState::InProgress => match self.start_time {
-                Some(start_time) => Instant::now() - start_time,
-                None => Duration::ZERO,
-            },
-            _ => self.display_time,
+    /// How many more flags the revealed number at `(x, y)` wants: its value
+    /// minus its flagged neighbours, negative when over-flagged. `None` for
+    /// anything that isn't a revealed number.
+    pub fn flags_needed(&self, x: Coord, y: Coord) -> Option<i8> {
+        let cell = self.cell(x, y);
+        (cell.state() == BoardCellState::Discovered && !cell.is_mine() && cell.value() > 0)
+            .then(|| cell.value() as i8 - self.flagged_neighbors(x, y) as i8)
+    }
+
+    /// Local sanity check of the player's flags: false if any revealed
+    /// number has more flagged neighbours than its value. This does not
+    /// solve the board, so a consistent result doesn't mean flags are right.
+    pub fn flags_consistent(&self) -> bool {
+        iproduct!(0..self.rows, 0..self.cols).all(|(x, y)| {
+            let cell = &self.board[x][y];
+            cell.state() != BoardCellState::Discovered
+                || cell.is_mine()
+                || self.flagged_neighbors(x as Coord, y as Coord) <= cell.value() as usize
+        })
+    }
+
+    /// Whether the flags could be exactly the mines: there are `mines` of
+    /// them, and since that leaves every other covered cell safe, each
+    /// revealed number must see exactly its value in flags. Stricter than
+    /// `flags_consistent`, which only rules out over-flagged numbers.
+    pub fn claimed_flags_feasible(&self) -> bool {
+        self.flagged_cells == self.mines as i16
+            && iproduct!(0..self.rows, 0..self.cols).all(|(x, y)| {
+                let cell = &self.board[x][y];
+                cell.state() != BoardCellState::Discovered
+                    || cell.is_mine()
+                    || self.flagged_neighbors(x as Coord, y as Coord) == cell.value() as usize
+            })
+    }
+
+    /// Whether revealing every unflagged covered cell would win right now,
+    /// i.e. the flags sit exactly on the remaining mines. This peeks at the
+    /// hidden layout, so it's meant for an auto-finish assist, not as
+    /// something the player could deduce.
+    pub fn would_win_if_flags_correct(&self) -> bool {
+        self.start
+            && self.game_state == GameState::InProgress
+            && self
+                .board
+                .iter()
+                .flatten()
+                .filter(|cell| cell.is_covered())
+                .all(|cell| cell.is_mine() == (cell.state() == BoardCellState::Flagged))
+    }
+
+    /// Whether `action` at `(x, y)` would change anything: the game must be
+    /// in progress, the cell on the board and in a state the action acts on.
+    /// A discovered number only counts when chording it would open a cell.
+    pub fn is_legal(&self, action: MoveKind, x: Coord, y: Coord) -> bool {
+        if self.game_state != GameState::InProgress || idx(x) >= self.rows || idx(y) >= self.cols {
+            return false;
+        }
+        match (action, self.cell(x, y).state()) {
+            (_, BoardCellState::Discovered) => {
+                self.flagged_neighbors(x, y) == self.cell(x, y).value() as usize
+                    && self
+                        .neighbors(x, y)
+                        .any(|(x1, y1)| self.cell(x1, y1).state() == BoardCellState::Blank)
+            }
+            (MoveKind::Click, BoardCellState::Blank) => true,
+            (MoveKind::Click, BoardCellState::Flagged) => self.click_unflag,
+            (MoveKind::Flag, state) => matches!(
+                state,
+                BoardCellState::Blank | BoardCellState::Flagged | BoardCellState::Question
+            ),
+            _ => false,
         }
     }
 
-    pub fn get_flagged_cells(&self) -> i16 {
-        self.flagged_cells
+    fn flagged_neighbors(&self, x: Coord, y: Coord) -> usize {
+        self.neighbors(x, y)
+            .filter(|&(x1, y1)| self.cell(x1, y1).state() == BoardCellState::Flagged)
+            .count()
     }
 
-    pub fn get_board_cells(&self) -> &BoardCells {
-        &self.board
+    /// Double-click style input: chords a discovered number, reveals a
+    /// covered cell and ignores flagged or questioned cells.
+    pub fn activate(&mut self, x: Coord, y: Coord) -> GameState {
+        match self.cell(x, y).state() {
+            BoardCellState::Discovered | BoardCellState::Blank => self.click(x, y),
+            _ => (),
+        }
+        self.game_state
     }
 
-    pub fn update(&mut self) {
-        self.display_time = self.get_display_time();
+    /// Power-up reveal of the plus-shaped area reaching `radius` cells out
+    /// from `(x, y)` along its row and column. Mines in the arms are skipped
+    /// and left covered instead of exploding, so this can never lose the
+    /// game; safe cells are clicked normally, so zeros still cascade.
+    /// Returns every cell that was revealed, cascades included.
+    pub fn reveal_cross(&mut self, x: Coord, y: Coord, radius: usize) -> Vec<Position> {
+        self.last_action_time = Some(self.clock.now());
+        if !self.start {
+            self.start(x, y, true);
+        }
         if self.game_state != GameState::InProgress {
-            for x in 0..self.rows {
-                for y in 0..self.cols {
-                    if self.board[x][y].value() != 15 {
-                        self.board[x][y].click();
-                    } else if self.game_state == GameState::Won {
-                        self.board[x][y].cell = 15 + ((BoardCellState::Flagged as u8) << 4);
-                    } else if self.board[x][y].state() != BoardCellState::Exploded {
-                        self.board[x][y].cell = 15;
-                    }
+            return Vec::new();
+        }
+        let (cx, cy) = (idx(x), idx(y));
+        let arms = (0..=radius).flat_map(|d| {
+            [
+                (cx.checked_sub(d), Some(cy)),
+                (cx.checked_add(d), Some(cy)),
+                (Some(cx), cy.checked_sub(d)),
+                (Some(cx), cy.checked_add(d)),
+            ]
+        });
+        for (x1, y1) in arms.filter_map(|(x1, y1)| Some((x1?, y1?))) {
+            if x1 >= self.rows || y1 >= self.cols {
+                continue;
+            }
+            let (x1, y1) = (x1 as Coord, y1 as Coord);
+            let cell = self.cell(x1, y1);
+            if cell.state() == BoardCellState::Blank && cell.value() != 15 {
+                self.enqueue_reveal(x1, y1, 0);
+            }
+        }
+        self.reveal_waves.clear();
+        let revealed = self.reveal();
+        self.check_won();
+        revealed
+    }
+
+    /// Chooses the neighbourhood numbers are counted over, `Adjacency::Eight`
+    /// by default. Numbers are computed when the first click places the
+    /// mines, so this is ignored once the game has started.
+    pub fn set_adjacency(&mut self, adjacency: Adjacency) {
+        if !self.start {
+            self.adjacency = adjacency;
+        }
+    }
+
+    /// How far revealing a zero spreads, `FloodMode::Full` by default.
+    pub fn set_flood_mode(&mut self, mode: FloodMode) {
+        self.flood_mode = mode;
+    }
+
+    /// Caps how many cells the cascade from a single click (or a
+    /// `continue_reveal` call) opens; the clicked cell itself always opens
+    /// at once. A cascade that hits the cap is left queued for
+    /// `continue_reveal`. `None`, the default, reveals everything at once.
+    pub fn set_max_reveal_per_click(&mut self, limit: Option<usize>) {
+        self.max_reveal_per_click = limit;
+    }
+
+    /// Resumes a cascade left unfinished by `max_reveal_per_click`, opening
+    /// up to another budget's worth of cells and returning them.
+    pub fn continue_reveal(&mut self) -> Vec<Position> {
+        if self.game_state != GameState::InProgress {
+            return Vec::new();
+        }
+        let revealed = self.reveal();
+        self.check_won();
+        revealed
+    }
+
+    /// How many cells the latest click (or `reveal_cross`) opened at each
+    /// cascade distance from the cells it opened directly, which are at
+    /// index 0. `continue_reveal` adds to the same waves.
+    pub fn last_reveal_waves(&self) -> &[usize] {
+        &self.reveal_waves
+    }
+
+    /// Cells queued by a reveal that hit `max_reveal_per_click`, waiting for
+    /// `continue_reveal`.
+    pub fn pending_reveals(&self) -> usize {
+        self.reveal_queue.len()
+    }
+
+    /// Size in cells of the visited bitmap used by reveals. It is allocated
+    /// once with the board and reused by every click.
+    pub fn reveal_buffer_capacity(&self) -> usize {
+        self.reveal_visited.capacity() * 64
+    }
+
+    /// Queues `(x, y)` for `reveal`, `depth` being how many cascade steps
+    /// away from the player's own click it is.
+    fn enqueue_reveal(&mut self, x: Coord, y: Coord, depth: u32) {
+        let i = idx(x) * self.cols + idx(y);
+        let (word, bit) = (i / 64, 1 << (i % 64));
+        if self.reveal_visited[word] & bit == 0 {
+            self.reveal_visited[word] |= bit;
+            self.reveal_queue.push_back(((x, y), depth));
+        }
+    }
+
+    fn reveal(&mut self) -> Vec<Position> {
+        let mut revealed = Vec::new();
+        let mut budget = self.max_reveal_per_click.unwrap_or(usize::MAX);
+        //Maybe optimize in future
+        while budget > 0 {
+            //BFS
+            let Some(((x, y), depth)) = self.reveal_queue.pop_front() else {
+                break;
+            };
+            // Cells flagged or opened since they were queued are skipped
+            // before the mine check, so a flag placed while a capped reveal
+            // is pending still protects its cell.
+            if self.cell(x, y).state() != BoardCellState::Blank {
+                continue;
+            }
+            if !self.open_cell(x, y, depth, &mut revealed) {
+                return revealed;
+            }
+            budget -= 1;
+        }
+        if self.reveal_queue.is_empty() {
+            self.reveal_visited.fill(0);
+        }
+        revealed
+    }
+
+    /// Opens the covered cell `(x, y)`, `depth` cascade steps from the
+    /// player's click, and queues its neighbours if it cascades. A mine
+    /// loses the game, drops any pending reveal and returns false.
+    fn open_cell(&mut self, x: Coord, y: Coord, depth: u32, revealed: &mut Vec<Position>) -> bool {
+        if self.cell(x, y).value() == 15 {
+            self.record_reveal(x, y);
+            self.cell_mut(x, y).click();
+            self.tick_timer();
+            self.game_state = GameState::Lost;
+            self.cell_mut(x, y).cell = 15 + (4 << 4);
+            self.reveal_queue.clear();
+            self.reveal_visited.fill(0);
+            return false;
+        }
+        let cascade = self.cell_mut(x, y).click();
+        self.clicked_cells += 1;
+        self.record_reveal(x, y);
+        revealed.push((x, y));
+        let wave = depth as usize;
+        if self.reveal_waves.len() <= wave {
+            self.reveal_waves.resize(wave + 1, 0);
+        }
+        self.reveal_waves[wave] += 1;
+        let cascade = cascade
+            && match self.flood_mode {
+                FloodMode::Full => true,
+                FloodMode::SingleRing => depth == 0,
+                FloodMode::None => false,
+            };
+        if cascade {
+            for (x1, y1) in self.neighbors(x, y) {
+                if self.cell(x1, y1).state() == BoardCellState::Blank {
+                    self.enqueue_reveal(x1, y1, depth + 1);
                 }
             }
         }
+        true
     }
-}
 
-impl Default for Board {
-    fn default() -> Self {
+    /// Debug/tooling helper: opens every safe cell regardless of whether it
+    /// could be deduced, then ends the game as a win the same way a real
+    /// win would (timer frozen, mines flagged by `finalize`). Flags on safe
+    /// cells are removed, and each opened cell gets the next `reveal_order`
+    /// in row-major order. Only acts on a started game still in progress.
+    pub fn debug_reveal_all_safe(&mut self) {
+        if !self.start || self.game_state != GameState::InProgress {
+            return;
+        }
+        self.tick_timer();
+        for (x, y) in iproduct!(0..self.rows, 0..self.cols).map(|(x, y)| (x as Coord, y as Coord)) {
+            let cell = self.cell(x, y).clone();
+            if cell.is_mine() || cell.state() == BoardCellState::Discovered {
+                continue;
+            }
+            if cell.state() == BoardCellState::Flagged {
+                self.flagged_cells -= 1;
+            }
+            *self.cell_mut(x, y) =
+                BoardCell::from_raw_parts(cell.value(), BoardCellState::Discovered);
+            self.clicked_cells += 1;
+            self.record_reveal(x, y);
+        }
+        self.check_won();
+        self.finalize();
+    }
+
+    fn record_reveal(&mut self, x: Coord, y: Coord) {
+        self.reveal_order[idx(x)][idx(y)] = Some(self.reveal_counter);
+        self.reveal_counter += 1;
+    }
+
+    fn check_won(&mut self) {
+        log::debug!("Check if game is won");
+        if self.game_state == GameState::InProgress
+            && self.clicked_cells + self.mines == self.cols * self.rows
+        {
+            self.tick_timer();
+            self.game_state = GameState::Won;
+            self.player_flagged_all_mines = self
+                .board
+                .iter()
+                .flatten()
+                .filter(|cell| cell.is_mine())
+                .all(|cell| cell.state() == BoardCellState::Flagged);
+        }
+    }
+
+    /// When enabled, clicking a flagged cell clears the flag instead of
+    /// being ignored. Off by default.
+    pub fn set_click_unflag(&mut self, enabled: bool) {
+        self.click_unflag = enabled;
+    }
+
+    /// Whether every mine was flagged by the player when the game was won,
+    /// as opposed to being auto-flagged by `update` afterwards.
+    pub fn was_flawless_flagging(&self) -> bool {
+        self.player_flagged_all_mines
+    }
+
+    /// Returns why the game ended the first time it is called after the
+    /// game is over, and `None` on every other call.
+    pub fn take_transition(&mut self) -> Option<EndReason> {
+        if self.transition_consumed {
+            return None;
+        }
+        let reason = match self.game_state {
+            GameState::InProgress => return None,
+            GameState::Won => EndReason::Won,
+            GameState::Lost => EndReason::Lost,
+        };
+        self.transition_consumed = true;
+        Some(reason)
+    }
+
+    pub fn get_display_time(&self) -> Duration {
+        match self.game_state {
+            GameState::InProgress => match self.start_time {
+                Some(start_time) => self.clock.now() - start_time,
+                None => Duration::ZERO,
+            },
+            _ => self.display_time,
+        }
+    }
+
+    /// Time since the last click or flag, `ZERO` before the first one.
+    pub fn time_since_last_action(&self) -> Duration {
+        match self.last_action_time {
+            Some(time) => self.clock.now() - time,
+            None => Duration::ZERO,
+        }
+    }
+
+    pub fn get_flagged_cells(&self) -> i16 {
+        self.flagged_cells
+    }
+
+    /// Plain integer view of what the player sees, see `NUMERIC_COVERED`,
+    /// `NUMERIC_FLAGGED` and `NUMERIC_MINE`. Question marks count as covered
+    /// and the exploded mine as a mine.
+    pub fn to_numeric_grid(&self) -> Vec<Vec<i8>> {
+        self.board
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell.state() {
+                        BoardCellState::Discovered if cell.is_mine() => NUMERIC_MINE,
+                        BoardCellState::Discovered => cell.value() as i8,
+                        BoardCellState::Exploded => NUMERIC_MINE,
+                        BoardCellState::Flagged => NUMERIC_FLAGGED,
+                        _ => NUMERIC_COVERED,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Inverse of `to_numeric_grid`. The grid only holds what was visible,
+    /// so flagged cells are taken to be mines and covered cells to be safe;
+    /// the result is meant for analysing a position, not for further play.
+    pub fn from_numeric_grid(grid: &[Vec<i8>]) -> Result<Board, MinesweeperError> {
+        let (rows, cols) = (grid.len(), grid.first().map_or(0, Vec::len));
+        if rows == 0 || cols == 0 {
+            return Err(MinesweeperError::InvalidDimensions { rows, cols });
+        }
+        let mut board = Board::new(rows, cols, 0);
+        for (x, row) in grid.iter().enumerate() {
+            if row.len() != cols {
+                return Err(MinesweeperError::ParseError(format!(
+                    "row {x} has {} cells, expected {cols}",
+                    row.len()
+                )));
+            }
+            for (y, &code) in row.iter().enumerate() {
+                board.board[x][y] = match code {
+                    0..=8 => {
+                        board.clicked_cells += 1;
+                        BoardCell::from_raw_parts(code as u8, BoardCellState::Discovered)
+                    }
+                    NUMERIC_COVERED => BoardCell::from_raw_parts(0, BoardCellState::Blank),
+                    NUMERIC_FLAGGED => {
+                        board.mines += 1;
+                        board.flagged_cells += 1;
+                        BoardCell::from_raw_parts(15, BoardCellState::Flagged)
+                    }
+                    NUMERIC_MINE => {
+                        board.mines += 1;
+                        BoardCell::from_raw_parts(15, BoardCellState::Discovered)
+                    }
+                    _ => {
+                        return Err(MinesweeperError::ParseError(format!(
+                            "invalid cell code {code} at ({x}, {y})"
+                        )))
+                    }
+                };
+            }
+        }
+        board.start = true;
+        Ok(board)
+    }
+
+    /// The cells of row `x`, left to right. Panics if `x` is off the board.
+    pub fn row(&self, x: Coord) -> &[BoardCell] {
+        &self.board[idx(x)]
+    }
+
+    /// The cells of column `y`, top to bottom. Panics if `y` is off the
+    /// board.
+    pub fn column(&self, y: Coord) -> Vec<&BoardCell> {
+        self.board.iter().map(|row| &row[idx(y)]).collect()
+    }
+
+    /// Recomputes the flag counter from the grid, for when cells were
+    /// modified without going through `flag`.
+    pub fn recount_flags(&mut self) {
+        self.flagged_cells = self
+            .board
+            .iter()
+            .flatten()
+            .filter(|cell| cell.state() == BoardCellState::Flagged)
+            .count() as i16;
+    }
+
+    pub fn get_board_cells(&self) -> &BoardCells {
+        &self.board
+    }
+
+    /// Splits every cell into `(safe, mines)` according to the generated
+    /// layout, ignoring play state. Mines are only placed by `start`, so
+    /// before the first click every cell is reported as safe.
+    pub fn solution(&self) -> (Vec<Position>, Vec<Position>) {
+        iproduct!(0..self.rows, 0..self.cols)
+            .map(|(x, y)| (x as Coord, y as Coord))
+            .partition(|&(x, y)| !self.cell(x, y).is_mine())
+    }
+
+    /// The position of each cell in the sequence of player reveals, counted
+    /// from 0 across the whole game (cascades included), or `None` if the
+    /// player has not revealed it.
+    pub fn reveal_order(&self) -> &[Vec<Option<u32>>] {
+        &self.reveal_order
+    }
+
+    /// Every cell the player has revealed, in the order it opened, as
+    /// recorded in `reveal_order`. Cascades appear in the order the reveal
+    /// walked them, and a lost game ends with the mine that was hit.
+    pub fn revealed_in_order(&self) -> Vec<Position> {
+        let mut revealed: Vec<(u32, Position)> = iproduct!(0..self.rows, 0..self.cols)
+            .filter_map(|(x, y)| Some((self.reveal_order[x][y]?, (x as Coord, y as Coord))))
+            .collect();
+        revealed.sort_unstable();
+        revealed.into_iter().map(|(_, position)| position).collect()
+    }
+
+    /// Rotates the board a quarter turn clockwise, swapping its dimensions.
+    pub fn rotate90(&self) -> Board {
+        let rows = self.rows;
+        self.transformed(self.cols, self.rows, |x, y| (rows - 1 - y, x))
+    }
+
+    pub fn flip_horizontal(&self) -> Board {
+        let cols = self.cols;
+        self.transformed(self.rows, self.cols, |x, y| (x, cols - 1 - y))
+    }
+
+    pub fn flip_vertical(&self) -> Board {
+        let rows = self.rows;
+        self.transformed(self.rows, self.cols, |x, y| (rows - 1 - x, y))
+    }
+
+    /// The symmetries that leave the mine layout unchanged; `Identity` is
+    /// always included. Only meaningful once mines have been placed.
+    pub fn symmetries(&self) -> Vec<Symmetry> {
+        Symmetry::ALL
+            .into_iter()
+            .filter(|&symmetry| {
+                (self.rows == self.cols || !symmetry.swaps_dimensions())
+                    && iproduct!(0..self.rows, 0..self.cols).all(|(x, y)| {
+                        let (sx, sy) = symmetry.source(self.rows, self.cols, x, y);
+                        self.board[x][y].is_mine() == self.board[sx][sy].is_mine()
+                    })
+            })
+            .collect()
+    }
+
+    /// Builds a `rows`x`cols` board whose cell `(x, y)` is this board's cell
+    /// `source(x, y)`, keeping the play state. The solver and any pending
+    /// chunked reveal are not carried over since they refer to the old
+    /// geometry.
+    fn transformed(
+        &self,
+        rows: usize,
+        cols: usize,
+        source: impl Fn(usize, usize) -> (usize, usize),
+    ) -> Board {
+        Board {
+            board: remap(&self.board, rows, cols, &source),
+            rows,
+            cols,
+            mines: self.mines,
+            game_state: self.game_state,
+            start: self.start,
+            clicked_cells: self.clicked_cells,
+            flagged_cells: self.flagged_cells,
+            start_time: self.start_time,
+            display_time: self.display_time,
+            solver: None,
+            click_unflag: self.click_unflag,
+            player_flagged_all_mines: self.player_flagged_all_mines,
+            reveal_order: remap(&self.reveal_order, rows, cols, &source),
+            flag_use: remap(&self.flag_use, rows, cols, &source),
+            reveal_counter: self.reveal_counter,
+            transition_consumed: self.transition_consumed,
+            max_reveal_per_click: self.max_reveal_per_click,
+            split_covered_view: self.split_covered_view,
+            mine_mobility: self.mine_mobility,
+            auto_complete: self.auto_complete,
+            flood_mode: self.flood_mode,
+            adjacency: self.adjacency,
+            reveal_queue: VecDeque::new(),
+            reveal_visited: vec![0; (rows * cols).div_ceil(64)],
+            reveal_waves: Vec::new(),
+            last_action_time: self.last_action_time,
+            clock: self.clock.clone(),
+        }
+    }
+
+    pub fn view(&self) -> Vec<Vec<CellView>> {
+        self.board
+            .iter()
+            .enumerate()
+            .map(|(x, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(y, cell)| match self.cell_view(cell) {
+                        CellView::Covered if self.split_covered_view => {
+                            if self.is_frontier(x as Coord, y as Coord) {
+                                CellView::CoveredFrontier
+                            } else {
+                                CellView::CoveredInterior
+                            }
+                        }
+                        view => view,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// When enabled, `view` tells covered cells next to a revealed number
+    /// (`CoveredFrontier`) apart from the rest (`CoveredInterior`). Off by
+    /// default, since it costs a neighbour scan per covered cell.
+    pub fn set_split_covered_view(&mut self, enabled: bool) {
+        self.split_covered_view = enabled;
+    }
+
+    fn cell_view(&self, cell: &BoardCell) -> CellView {
+        match cell.state() {
+            BoardCellState::Discovered if cell.is_mine() => CellView::Mine,
+            BoardCellState::Discovered => CellView::Revealed(cell.value()),
+            BoardCellState::Flagged if self.game_state == GameState::Lost && !cell.is_mine() => {
+                CellView::WrongFlag
+            }
+            BoardCellState::Flagged => CellView::Flagged,
+            BoardCellState::Question => CellView::Question,
+            BoardCellState::Exploded => CellView::Exploded,
+            BoardCellState::Blank | BoardCellState::Other => CellView::Covered,
+        }
+    }
+
+    pub fn render_with<R: CellRenderer>(&self, renderer: &R) -> Vec<Vec<R::Output>> {
+        self.board
+            .iter()
+            .map(|row| row.iter().map(|cell| renderer.render_cell(cell)).collect())
+            .collect()
+    }
+
+    pub fn value_histogram(&self) -> [usize; 9] {
+        let mut histogram = [0; 9];
+        for cell in self.board.iter().flatten() {
+            if cell.state() == BoardCellState::Discovered && !cell.is_mine() {
+                histogram[cell.value() as usize] += 1;
+            }
+        }
+        histogram
+    }
+
+    /// Copies the inclusive rectangle from `(x0, y0)` to `(x1, y1)` into a new
+    /// board of its own, keeping each cell's mine, reveal and flag state and
+    /// the game state. Numbers are recounted inside the rectangle, so along
+    /// its edges they won't match the original where mines were left
+    /// outside. The copy has no solver. Fails if the rectangle is empty or
+    /// leaves the board.
+    pub fn subboard(
+        &self,
+        x0: Coord,
+        y0: Coord,
+        x1: Coord,
+        y1: Coord,
+    ) -> Result<Board, MinesweeperError> {
+        let (x0, y0, x1, y1) = (idx(x0), idx(y0), idx(x1), idx(y1));
+        if x1 >= self.rows || y1 >= self.cols {
+            return Err(MinesweeperError::OutOfBounds { x: x1, y: y1 });
+        }
+        let (rows, cols) = ((x1 + 1).saturating_sub(x0), (y1 + 1).saturating_sub(y0));
+        if rows == 0 || cols == 0 {
+            return Err(MinesweeperError::InvalidDimensions { rows, cols });
+        }
+        let mut sub = Board::new(rows, cols, 0);
+        sub.adjacency = self.adjacency;
+        for (x, y) in iproduct!(0..rows, 0..cols) {
+            let cell = &self.board[x0 + x][y0 + y];
+            let value = if cell.is_mine() { 15 } else { 0 };
+            sub.board[x][y] = BoardCell::from_raw_parts(value, cell.state());
+        }
+        for (x, y) in iproduct!(0..rows, 0..cols) {
+            let cell = sub.board[x][y].clone();
+            if cell.is_mine() {
+                sub.mines += 1;
+                continue;
+            }
+            let value = sub.adjacent_mine_count(x as Coord, y as Coord);
+            sub.board[x][y] = BoardCell::from_raw_parts(value, cell.state());
+            if cell.state() == BoardCellState::Discovered {
+                sub.clicked_cells += 1;
+            }
+        }
+        sub.recount_flags();
+        sub.start = self.start;
+        sub.game_state = self.game_state;
+        Ok(sub)
+    }
+
+    /// Mines in the inclusive rectangle from `(x0, y0)` to `(x1, y1)`, clamped
+    /// to the board. An empty rectangle (`x0 > x1` or `y0 > y1`) has none.
+    /// Mines only exist once the board has been started.
+    pub fn mines_in_region(&self, x0: Coord, y0: Coord, x1: Coord, y1: Coord) -> usize {
+        let (x1, y1) = (
+            idx(x1).min(self.rows.saturating_sub(1)),
+            idx(y1).min(self.cols.saturating_sub(1)),
+        );
+        iproduct!(idx(x0)..=x1, idx(y0)..=y1)
+            .filter(|&(x, y)| self.board[x][y].is_mine())
+            .count()
+    }
+
+    /// Connected components of zero cells in the generated layout, i.e.
+    /// the areas a single click cascades through. Each region lists its
+    /// zeros followed by the numbered cells bordering it; a numbered cell
+    /// touching several regions appears in each of them.
+    pub fn zero_regions(&self) -> Vec<Vec<Position>> {
+        let mut seen = vec![vec![false; self.cols]; self.rows];
+        let mut regions = Vec::new();
+        for (x, y) in iproduct!(0..self.rows, 0..self.cols) {
+            if seen[x][y] || self.board[x][y].value() != 0 {
+                continue;
+            }
+            seen[x][y] = true;
+            let mut zeros = vec![(x as Coord, y as Coord)];
+            let mut border = Vec::new();
+            let mut border_set = HashSet::new();
+            let mut i = 0;
+            while let Some(&(x, y)) = zeros.get(i) {
+                i += 1;
+                for (x1, y1) in self.neighbors(x, y) {
+                    if self.cell(x1, y1).value() == 0 {
+                        if !seen[idx(x1)][idx(y1)] {
+                            seen[idx(x1)][idx(y1)] = true;
+                            zeros.push((x1, y1));
+                        }
+                    } else if border_set.insert((x1, y1)) {
+                        border.push((x1, y1));
+                    }
+                }
+            }
+            zeros.extend(border);
+            regions.push(zeros);
+        }
+        regions
+    }
+
+    /// The board's 3BV: the fewest clicks that clear it without chording,
+    /// one per zero region plus one per number not bordering any zero.
+    pub fn three_bv(&self) -> usize {
+        let regions = self.zero_regions();
+        let mut bordered = HashSet::new();
+        for &position in regions.iter().flatten() {
+            bordered.insert(position);
+        }
+        let isolated = iproduct!(0..self.rows, 0..self.cols)
+            .filter(|&(x, y)| {
+                let cell = &self.board[x][y];
+                !cell.is_mine()
+                    && cell.value() != 0
+                    && !bordered.contains(&(x as Coord, y as Coord))
+            })
+            .count();
+        regions.len() + isolated
+    }
+
+    /// `three_bv` and `region_count` from a single sweep over the board,
+    /// for processing many boards. Zero regions are flooded as the sweep
+    /// reaches them, and each revealed cell joins its unflagged frontier
+    /// neighbours in a union-find, so neither metric builds the region
+    /// lists or hash sets its own method does.
+    pub fn analyze(&self) -> BoardAnalysis {
+        let cells = self.rows * self.cols;
+        let at = |(x, y): Position| idx(x) * self.cols + idx(y);
+        let mut flooded = vec![false; cells];
+        let mut bordered = vec![false; cells];
+        let mut frontier = vec![false; cells];
+        let mut parent: Vec<usize> = (0..cells).collect();
+        let (mut zero_regions, mut numbers, mut bordered_numbers) = (0, 0, 0);
+        for (x, y) in iproduct!(0..self.rows, 0..self.cols).map(|(x, y)| (x as Coord, y as Coord)) {
+            let cell = self.cell(x, y);
+            if !cell.is_mine() && cell.value() != 0 {
+                numbers += 1;
+            }
+            if cell.value() == 0 && !flooded[at((x, y))] {
+                zero_regions += 1;
+                flooded[at((x, y))] = true;
+                let mut zeros = vec![(x, y)];
+                while let Some((x, y)) = zeros.pop() {
+                    for position in self.neighbors(x, y) {
+                        let i = at(position);
+                        if self.cell(position.0, position.1).value() == 0 {
+                            if !flooded[i] {
+                                flooded[i] = true;
+                                zeros.push(position);
+                            }
+                        } else if !bordered[i] {
+                            bordered[i] = true;
+                            bordered_numbers += 1;
+                        }
+                    }
+                }
+            }
+            if cell.state() == BoardCellState::Discovered && !cell.is_mine() {
+                let mut group = None;
+                for (x1, y1) in self.neighbors(x, y) {
+                    if self.cell(x1, y1).state() == BoardCellState::Flagged
+                        || !self.is_frontier(x1, y1)
+                    {
+                        continue;
+                    }
+                    let i = at((x1, y1));
+                    frontier[i] = true;
+                    let root = find_root(&mut parent, i);
+                    match group {
+                        Some(group) => parent[root] = group,
+                        None => group = Some(root),
+                    }
+                }
+            }
+        }
+        let region_count = (0..cells)
+            .filter(|&i| frontier[i] && find_root(&mut parent, i) == i)
+            .count();
+        BoardAnalysis {
+            three_bv: zero_regions + numbers - bordered_numbers,
+            region_count,
+        }
+    }
+
+    /// A short, paste-friendly summary of the game: a header line with
+    /// dimensions and mines, a line with the outcome, time in seconds and
+    /// 3BV, then one line per row with 🟩 for cells the player revealed,
+    /// 💣 for mines, 💥 for the mine that was hit and ⬜ for the rest.
+    /// Mines stay ⬜ until the game is over, and the time is read from
+    /// the clock, so no `update` is needed first.
+    pub fn share_text(&self) -> String {
+        let outcome = match self.game_state {
+            GameState::Won => "Won",
+            GameState::Lost => "Lost",
+            GameState::InProgress => "In progress",
+        };
+        let mut text = format!(
+            "Minesweeper {}x{} ({} mines)\n{} in {:.1}s | 3BV {}\n",
+            self.rows,
+            self.cols,
+            self.mines,
+            outcome,
+            self.get_display_time().as_secs_f64(),
+            self.three_bv()
+        );
+        for (x, row) in self.board.iter().enumerate() {
+            for (y, cell) in row.iter().enumerate() {
+                text.push(if cell.state() == BoardCellState::Exploded {
+                    '💥'
+                } else if cell.is_mine() && self.game_state != GameState::InProgress {
+                    '💣'
+                } else if self.reveal_order[x][y].is_some() {
+                    '🟩'
+                } else {
+                    '⬜'
+                });
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    /// For every cell, how many cells a first click there would open under
+    /// the full cascade: the size of its zero region for a zero, 1 for a
+    /// number and 0 for a mine. Read from the generated layout, via
+    /// `zero_regions` rather than a flood per cell.
+    pub fn opening_qualities(&self) -> Vec<Vec<usize>> {
+        let mut qualities: Vec<Vec<usize>> = self
+            .board
+            .iter()
+            .map(|row| row.iter().map(|cell| (!cell.is_mine()) as usize).collect())
+            .collect();
+        for region in self.zero_regions() {
+            for &(x, y) in &region {
+                if self.cell(x, y).value() == 0 {
+                    qualities[idx(x)][idx(y)] = region.len();
+                }
+            }
+        }
+        qualities
+    }
+
+    /// How many more cells this board has revealed than `other`.
+    pub fn progress_diff(&self, other: &Board) -> i64 {
+        self.clicked_cells as i64 - other.clicked_cells as i64
+    }
+
+    pub fn same_layout(&self, other: &Board) -> bool {
+        self.rows == other.rows
+            && self.cols == other.cols
+            && zip(self.board.iter().flatten(), other.board.iter().flatten())
+                .all(|(a, b)| a.is_mine() == b.is_mine())
+    }
+
+    /// Hash of the dimensions and mine positions only, so it stays the same
+    /// however far the board has been played. Uses 64-bit FNV-1a so the
+    /// value is reproducible across runs and platforms.
+    pub fn layout_hash(&self) -> u64 {
+        let mut hash = FNV_OFFSET_BASIS;
+        let dimensions = [self.rows as u64, self.cols as u64];
+        let bytes = dimensions
+            .iter()
+            .flat_map(|d| d.to_le_bytes())
+            .chain(self.board.iter().flatten().map(|cell| cell.is_mine() as u8));
+        for byte in bytes {
+            hash = (hash ^ byte as u64).wrapping_mul(FNV_PRIME);
+        }
+        hash
+    }
+
+    /// Covered cells touching at least one revealed number.
+    pub fn frontier_cells(&self) -> Vec<Position> {
+        iproduct!(0..self.rows, 0..self.cols)
+            .map(|(x, y)| (x as Coord, y as Coord))
+            .filter(|&(x, y)| self.is_frontier(x, y))
+            .collect()
+    }
+
+    /// Every way of placing mines on the unflagged frontier that agrees with
+    /// the revealed numbers and the remaining mine count, stopping after
+    /// `max` arrangements. Flags are taken as mines, cells next to a
+    /// revealed zero are never mines (they can border one with
+    /// `FloodMode::None`), and mines left off the frontier can only go to
+    /// unconstrained cells. Returns `None` when the frontier has more than
+    /// `MAX_ENUMERATED_FRONTIER` cells.
+    pub fn enumerate_arrangements(&self, max: usize) -> Option<Vec<Vec<Position>>> {
+        let (cells, mut search) = self.arrangement_search(Some(max))?;
+        search.run(0);
+        Some(
+            search
+                .found
+                .into_iter()
+                .map(|mines| mines.into_iter().map(|i| cells[i]).collect())
+                .collect(),
+        )
+    }
+
+    /// Mine probability of each `unconstrained_cells` cell. Every frontier
+    /// arrangement `enumerate_arrangements` would find is weighted by the
+    /// number of ways to place the remaining mines in the interior,
+    /// `C(interior, remaining - k)` for an arrangement of `k` mines; the
+    /// result is the weighted mean of `remaining - k`, divided by the
+    /// interior size. Flags count as mines. When the frontier is too large
+    /// to enumerate (or no arrangement fits) this falls back to the
+    /// remaining mines over all unflagged covered cells. 0.0 if there are
+    /// no unconstrained cells.
+    pub fn unconstrained_mine_probability(&self) -> f64 {
+        let interior = self.unconstrained_cells().len();
+        if interior == 0 {
+            return 0.0;
+        }
+        let remaining = self.remaining_unflagged_mines();
+        if let Some((_, mut search)) = self.arrangement_search(None) {
+            search.run(0);
+            let log_weights: Vec<(usize, f64)> = (search.tally.iter().enumerate())
+                .filter(|&(k, &count)| count > 0 && k <= remaining && remaining - k <= interior)
+                .map(|(k, &count)| (k, (count as f64).ln() + ln_choose(interior, remaining - k)))
+                .collect();
+            let top = log_weights
+                .iter()
+                .map(|&(_, w)| w)
+                .fold(f64::NEG_INFINITY, f64::max);
+            let (mut total, mut expected) = (0.0, 0.0);
+            for (k, w) in log_weights {
+                let w = (w - top).exp();
+                total += w;
+                expected += w * (remaining - k) as f64;
+            }
+            if total > 0.0 {
+                return expected / total / interior as f64;
+            }
+        }
+        let unknown = self
+            .board
+            .iter()
+            .flatten()
+            .filter(|cell| {
+                matches!(
+                    cell.state(),
+                    BoardCellState::Blank | BoardCellState::Question
+                )
+            })
+            .count();
+        remaining as f64 / unknown as f64
+    }
+
+    /// Expected information, in bits, from clicking `(x, y)`: the entropy
+    /// of what the click shows, a mine or one of the numbers `0..=8`.
+    /// Each outcome is a function of the full arrangement, so this is how
+    /// much the click is expected to cut the uncertainty about where the
+    /// mines are. Only the frontier groups holding the cell or one of its
+    /// neighbours are enumerated, grouped as `region_count` does. Each of
+    /// their arrangements is counted `C(outside, remaining - k)` times,
+    /// where the cells outside are every other frontier cell plus the
+    /// unconstrained ones, so the other groups are approximated as free
+    /// cells. Unconstrained neighbours (and the cell itself, if
+    /// unconstrained) are approximated as independent mines at the outside
+    /// density. 0.0 for a cell that isn't covered and unflagged, or when
+    /// its groups have more than `MAX_ENUMERATED_FRONTIER` cells.
+    pub fn expected_info_gain(&self, x: Coord, y: Coord) -> f64 {
+        if !matches!(
+            self.cell(x, y).state(),
+            BoardCellState::Blank | BoardCellState::Question
+        ) {
+            return 0.0;
+        }
+        let frontier = self.search_cells();
+        let groups = self.search_groups(&frontier);
+        let touched: Vec<Position> = once((x, y)).chain(self.neighbors(x, y)).collect();
+        let probed: HashSet<usize> = zip(&frontier, &groups)
+            .filter(|(position, _)| touched.contains(position))
+            .map(|(_, &group)| group)
+            .collect();
+        let cells: Vec<Position> = zip(&frontier, &groups)
+            .filter(|(_, group)| probed.contains(group))
+            .map(|(&position, _)| position)
+            .collect();
+        if cells.len() > MAX_ENUMERATED_FRONTIER {
+            return 0.0;
+        }
+        let unconstrained = self.unconstrained_cells();
+        let outside = unconstrained.len() + frontier.len() - cells.len();
+        let remaining = self.remaining_unflagged_mines();
+        let (cells, mut search) = self.search_over(cells, outside, None);
+        search.probe_cell = cells.iter().position(|&cell| cell == (x, y));
+        search.probe = (self.neighbors(x, y))
+            .filter_map(|position| cells.iter().position(|&cell| cell == position))
+            .collect();
+        search.outcomes = vec![[0; 10]; cells.len() + 1];
+        search.run(0);
+
+        let self_unconstrained = unconstrained.contains(&(x, y));
+        let flagged = self.flagged_neighbors(x, y);
+        let open = (self.neighbors(x, y))
+            .filter(|position| unconstrained.contains(position))
+            .count();
+        let log_weights: Vec<(usize, f64)> = (search.tally.iter().enumerate())
+            .filter(|&(k, &count)| count > 0 && k <= remaining && remaining - k <= outside)
+            .map(|(k, _)| (k, ln_choose(outside, remaining - k)))
+            .collect();
+        let top = log_weights
+            .iter()
+            .map(|&(_, w)| w)
+            .fold(f64::NEG_INFINITY, f64::max);
+        // outcome[9] is the mine, the rest are the number shown.
+        let mut outcome = [0.0; 10];
+        for (k, w) in log_weights {
+            let w = (w - top).exp();
+            let density = if outside == 0 {
+                0.0
+            } else {
+                (remaining - k) as f64 / outside as f64
+            };
+            let self_mine = if self_unconstrained { density } else { 0.0 };
+            for (seen, &count) in search.outcomes[k].iter().enumerate() {
+                let w = w * count as f64;
+                if seen == 9 {
+                    outcome[9] += w;
+                    continue;
+                }
+                outcome[9] += w * self_mine;
+                for hidden in 0..=open {
+                    let hidden_p = ln_choose(open, hidden).exp()
+                        * density.powi(hidden as i32)
+                        * (1.0 - density).powi((open - hidden) as i32);
+                    outcome[flagged + seen + hidden] += w * (1.0 - self_mine) * hidden_p;
+                }
+            }
+        }
+        let total: f64 = outcome.iter().sum();
+        if total <= 0.0 {
+            return 0.0;
+        }
+        outcome
+            .iter()
+            .filter(|&&w| w > 0.0)
+            .map(|&w| -(w / total) * (w / total).log2())
+            .sum()
+    }
+
+    /// Each frontier group with the range of unflagged mines it can hold:
+    /// the counts some arrangement of the group reaches while the other
+    /// groups and the unconstrained cells take the rest of the remaining
+    /// mines. Groups are `search_cells` split as `region_count` does; one
+    /// with more than `MAX_ENUMERATED_FRONTIER` cells is taken to hold
+    /// anything from none to all of its cells. Groups no arrangement fits
+    /// are left out.
+    pub fn region_mine_counts(&self) -> Vec<(Vec<Position>, RangeInclusive<u8>)> {
+        let remaining = self.remaining_unflagged_mines();
+        let interior = self.unconstrained_cells().len();
+        let groups = self.group_tallies();
+        let possible: Vec<Vec<bool>> = groups
+            .iter()
+            .map(|(cells, tally)| match tally {
+                Some(tally) => tally.iter().map(|&count| count > 0).collect(),
+                None => vec![true; cells.len() + 1],
+            })
+            .collect();
+        (groups.iter().enumerate())
+            .filter_map(|(g, (cells, _))| {
+                // Mine totals the other groups can hold between them.
+                let mut others = vec![true];
+                for (h, counts) in possible.iter().enumerate() {
+                    if h != g {
+                        others = or_convolve(&others, counts);
+                    }
+                }
+                let fits = |k: usize| {
+                    possible[g][k]
+                        && others.iter().enumerate().any(|(total, &ok)| {
+                            ok && k + total <= remaining && remaining - k - total <= interior
+                        })
+                };
+                let mut counts = (0..possible[g].len()).filter(|&k| fits(k));
+                let low = counts.next()?;
+                let high = counts.next_back().unwrap_or(low);
+                let clamp = |k: usize| k.min(u8::MAX as usize) as u8;
+                Some((cells.clone(), clamp(low)..=clamp(high)))
+            })
+            .collect()
+    }
+
+    /// Forced guesses: pairs of covered cells that form a frontier group of
+    /// their own holding exactly one mine (see `region_mine_counts`), are
+    /// touched by the same revealed numbers, so either could be the mine,
+    /// and have nothing but revealed or flagged cells around them besides
+    /// each other, so no later click elsewhere can tell them apart.
+    pub fn fifty_fifty_pairs(&self) -> Vec<(Position, Position)> {
+        let numbers = |(x, y): Position| -> Vec<Position> {
+            self.neighbors(x, y)
+                .filter(|&(x1, y1)| {
+                    let cell = self.cell(x1, y1);
+                    cell.state() == BoardCellState::Discovered && !cell.is_mine()
+                })
+                .collect()
+        };
+        let sealed = |(x, y): Position, other: Position| {
+            self.neighbors(x, y).all(|position| {
+                position == other
+                    || matches!(
+                        self.cell(position.0, position.1).state(),
+                        BoardCellState::Discovered | BoardCellState::Flagged
+                    )
+            })
+        };
+        self.region_mine_counts()
+            .into_iter()
+            .filter_map(|(cells, mines)| match cells[..] {
+                [a, b]
+                    if mines == (1..=1)
+                        && numbers(a) == numbers(b)
+                        && sealed(a, b)
+                        && sealed(b, a) =>
+                {
+                    Some((a, b))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Shannon entropy, in bits, of where the unflagged mines are: the log
+    /// of how many full layouts agree with the revealed numbers, the flags
+    /// and the remaining mine count, all taken as equally likely. Frontier
+    /// groups are counted on their own and combined by mine total, with
+    /// the unconstrained cells taking the rest. A group over
+    /// `MAX_ENUMERATED_FRONTIER` cells is approximated by ignoring its
+    /// numbers, which can only overstate the entropy. 0.0 when no layout
+    /// fits.
+    pub fn entropy(&self) -> f64 {
+        let remaining = self.remaining_unflagged_mines();
+        let interior = self.unconstrained_cells().len();
+        // ln of the number of frontier arrangements by mine total.
+        let mut totals = vec![0.0];
+        for (cells, tally) in self.group_tallies() {
+            let counts: Vec<f64> = match tally {
+                Some(tally) => tally.iter().map(|&count| (count as f64).ln()).collect(),
+                None => (0..=cells.len())
+                    .map(|k| ln_choose(cells.len(), k))
+                    .collect(),
+            };
+            totals = ln_convolve(&totals, &counts);
+        }
+        let layouts = (totals.iter().enumerate())
+            .filter(|&(k, &ways)| {
+                ways > f64::NEG_INFINITY && k <= remaining && remaining - k <= interior
+            })
+            .map(|(k, &ways)| ways + ln_choose(interior, remaining - k))
+            .fold(f64::NEG_INFINITY, ln_add);
+        if layouts == f64::NEG_INFINITY {
+            return 0.0;
+        }
+        layouts / std::f64::consts::LN_2
+    }
+
+    /// `search_cells` split into their groups, in row-major order of each
+    /// group's first cell, with the arrangement tally of each group searched
+    /// on its own (any mine count up to the remaining mines), or `None` for
+    /// groups over `MAX_ENUMERATED_FRONTIER` cells.
+    fn group_tallies(&self) -> Vec<(Vec<Position>, Option<Vec<u64>>)> {
+        let cells = self.search_cells();
+        let groups = self.search_groups(&cells);
+        let (mut roots, mut members): (Vec<usize>, Vec<Vec<Position>>) = (Vec::new(), Vec::new());
+        for (&position, &group) in zip(&cells, &groups) {
+            match roots.iter().position(|&root| root == group) {
+                Some(i) => members[i].push(position),
+                None => {
+                    roots.push(group);
+                    members.push(vec![position]);
+                }
+            }
+        }
+        members
+            .into_iter()
+            .map(|group| {
+                if group.len() > MAX_ENUMERATED_FRONTIER {
+                    return (group, None);
+                }
+                let (group, mut search) = self.search_over(group, usize::MAX, None);
+                search.run(0);
+                (group, Some(search.tally))
+            })
+            .collect()
+    }
+
+    fn remaining_unflagged_mines(&self) -> usize {
+        self.mines
+            .saturating_sub(self.flagged_cells.max(0) as usize)
+    }
+
+    /// Unflagged frontier cells whose state the arrangement search decides.
+    /// Cells next to a revealed zero are known safe, so they are left out
+    /// and add nothing to the constraints they touch.
+    fn search_cells(&self) -> Vec<Position> {
+        let next_to_zero = |x: Coord, y: Coord| {
+            self.neighbors(x, y).any(|(x1, y1)| {
+                let cell = self.cell(x1, y1);
+                cell.state() == BoardCellState::Discovered && cell.value() == 0
+            })
+        };
+        self.frontier_cells()
+            .into_iter()
+            .filter(|&(x, y)| {
+                self.cell(x, y).state() != BoardCellState::Flagged && !next_to_zero(x, y)
+            })
+            .collect()
+    }
+
+    /// Union-find root for each of `cells`, two cells sharing a root when
+    /// some revealed number touches both.
+    fn search_groups(&self, cells: &[Position]) -> Vec<usize> {
+        let mut parent: Vec<usize> = (0..cells.len()).collect();
+        for (x, y) in iproduct!(0..self.rows, 0..self.cols).map(|(x, y)| (x as Coord, y as Coord)) {
+            let cell = self.cell(x, y);
+            if cell.state() != BoardCellState::Discovered || cell.is_mine() {
+                continue;
+            }
+            let mut group = None;
+            for position in self.neighbors(x, y) {
+                let Some(i) = cells.iter().position(|&cell| cell == position) else {
+                    continue;
+                };
+                let root = find_root(&mut parent, i);
+                match group {
+                    Some(group) => parent[root] = group,
+                    None => group = Some(root),
+                }
+            }
+        }
+        (0..cells.len())
+            .map(|i| find_root(&mut parent, i))
+            .collect()
+    }
+
+    /// Sets up the backtracking shared by `enumerate_arrangements`,
+    /// `unconstrained_mine_probability` and `expected_info_gain` over
+    /// `search_cells`, or `None` if there are more than
+    /// `MAX_ENUMERATED_FRONTIER` of them.
+    fn arrangement_search(&self, max: Option<usize>) -> Option<(Vec<Position>, ArrangementSearch)> {
+        let cells = self.search_cells();
+        if cells.len() > MAX_ENUMERATED_FRONTIER {
+            return None;
+        }
+        let interior = self.unconstrained_cells().len();
+        Some(self.search_over(cells, interior, max))
+    }
+
+    /// Backtracking over `cells`, which must include every search cell that
+    /// shares a number with one of them. The mines they don't hold go to
+    /// the `outside` cells.
+    fn search_over(
+        &self,
+        cells: Vec<Position>,
+        outside: usize,
+        max: Option<usize>,
+    ) -> (Vec<Position>, ArrangementSearch) {
+        let remaining = self.remaining_unflagged_mines();
+        let mut search = ArrangementSearch {
+            cell_constraints: vec![Vec::new(); cells.len()],
+            need: Vec::new(),
+            open: Vec::new(),
+            mines: Vec::new(),
+            bounds: (remaining.saturating_sub(outside), remaining),
+            max,
+            found: Vec::new(),
+            tally: vec![0; cells.len() + 1],
+            probe_cell: None,
+            probe: Vec::new(),
+            outcomes: Vec::new(),
+        };
+        for (x, y) in iproduct!(0..self.rows, 0..self.cols).map(|(x, y)| (x as Coord, y as Coord)) {
+            let cell = self.cell(x, y);
+            if cell.state() != BoardCellState::Discovered || cell.is_mine() || cell.value() == 0 {
+                continue;
+            }
+            let touching: Vec<usize> = self
+                .neighbors(x, y)
+                .filter_map(|position| cells.iter().position(|&cell| cell == position))
+                .collect();
+            if touching.is_empty() {
+                continue;
+            }
+            for &i in &touching {
+                search.cell_constraints[i].push(search.need.len());
+            }
+            search
+                .need
+                .push(cell.value() as i32 - self.flagged_neighbors(x, y) as i32);
+            search.open.push(touching.len());
+        }
+        (cells, search)
+    }
+
+    /// Finds every 1-2-1 and 1-2-2-1 wall pattern on the board, in rows and
+    /// columns, that settles at least one covered cell. See `Pattern` for
+    /// what each one concludes.
+    pub fn detect_patterns(&self) -> Vec<PatternMatch> {
+        let mut found = Vec::new();
+        for pattern in [Pattern::OneTwoOne, Pattern::OneTwoTwoOne] {
+            for (x, y) in iproduct!(0..self.rows as i32, 0..self.cols as i32) {
+                for ((ax, ay), (sx, sy)) in [((0, 1), (1, 0)), ((1, 0), (0, 1))] {
+                    for side in [-1, 1] {
+                        let at = |j: i32, k: i32| {
+                            (x + ax * j + sx * k * side, y + ay * j + sy * k * side)
+                        };
+                        found.extend(self.match_pattern(pattern, at));
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    /// Tries `pattern` with its numbers at `at(j, 0)` and its side line at
+    /// `at(j, 1)`.
+    fn match_pattern(
+        &self,
+        pattern: Pattern,
+        at: impl Fn(i32, i32) -> (i32, i32),
+    ) -> Option<PatternMatch> {
+        let values = pattern.values();
+        let len = values.len() as i32;
+        let unknown = |(x, y): Position| {
+            matches!(
+                self.cell(x, y).state(),
+                BoardCellState::Blank | BoardCellState::Question
+            )
+        };
+        let side: Vec<Option<Position>> = (-1..=len).map(|j| self.on_board(at(j, 1))).collect();
+        let mut numbers = Vec::new();
+        for (j, &value) in values.iter().enumerate() {
+            let (x, y) = self.on_board(at(j as i32, 0))?;
+            let cell = self.cell(x, y);
+            if cell.state() != BoardCellState::Discovered
+                || cell.is_mine()
+                || cell.value() as usize != value as usize + self.flagged_neighbors(x, y)
+                || self
+                    .neighbors(x, y)
+                    .any(|n| unknown(n) && !side.contains(&Some(n)))
+            {
+                return None;
+            }
+            numbers.push((x, y));
+        }
+        let (mut mines, mut safe) = (Vec::new(), Vec::new());
+        for (j, position) in (-1..=len).zip(side) {
+            match position {
+                Some(position) if unknown(position) => {
+                    if pattern.mine_offsets().contains(&j) {
+                        mines.push(position);
+                    } else {
+                        safe.push(position);
+                    }
+                }
+                _ => (),
+            }
+        }
+        (!mines.is_empty() || !safe.is_empty()).then_some(PatternMatch {
+            pattern,
+            numbers,
+            mines,
+            safe,
+        })
+    }
+
+    fn on_board(&self, (x, y): (i32, i32)) -> Option<Position> {
+        (0 <= x && x < self.rows as i32 && 0 <= y && y < self.cols as i32)
+            .then_some((x as Coord, y as Coord))
+    }
+
+    /// How broadly the board has been opened: revealed cells divided by the
+    /// perimeter of the revealed region, where the perimeter counts each
+    /// side a revealed cell shares with a cell that isn't revealed (the
+    /// board's border doesn't count). A perimeter of zero counts as one.
+    /// Narrow paths score low and wide open areas high; 0.0 before anything
+    /// is revealed.
+    pub fn openness(&self) -> f64 {
+        let revealed = |x: usize, y: usize| {
+            let cell = &self.board[x][y];
+            cell.state() == BoardCellState::Discovered && !cell.is_mine()
+        };
+        let (mut area, mut perimeter) = (0usize, 0usize);
+        for (x, y) in iproduct!(0..self.rows, 0..self.cols).filter(|&(x, y)| revealed(x, y)) {
+            area += 1;
+            let sides = [
+                x.checked_sub(1).map(|x| (x, y)),
+                (x + 1 < self.rows).then_some((x + 1, y)),
+                y.checked_sub(1).map(|y| (x, y)),
+                (y + 1 < self.cols).then_some((x, y + 1)),
+            ];
+            perimeter += sides
+                .into_iter()
+                .flatten()
+                .filter(|&(x1, y1)| !revealed(x1, y1))
+                .count();
+        }
+        area as f64 / perimeter.max(1) as f64
+    }
+
+    /// Unflagged covered cells with no revealed neighbour at all. Nothing
+    /// local constrains them, so their only information is the overall
+    /// density of the remaining mines.
+    pub fn unconstrained_cells(&self) -> Vec<Position> {
+        iproduct!(0..self.rows, 0..self.cols)
+            .map(|(x, y)| (x as Coord, y as Coord))
+            .filter(|&(x, y)| {
+                matches!(
+                    self.cell(x, y).state(),
+                    BoardCellState::Blank | BoardCellState::Question
+                ) && self
+                    .neighbors(x, y)
+                    .all(|(x1, y1)| self.cell(x1, y1).state() != BoardCellState::Discovered)
+            })
+            .collect()
+    }
+
+    /// Number of independent groups on the unflagged frontier, where two
+    /// cells belong together when some revealed number touches both. A
+    /// cheap difficulty signal: many small groups are easier than one big
+    /// tangled one.
+    pub fn region_count(&self) -> usize {
+        let cells: HashSet<Position> = self
+            .frontier_cells()
+            .into_iter()
+            .filter(|&(x, y)| self.cell(x, y).state() != BoardCellState::Flagged)
+            .collect();
+        let mut seen = HashSet::new();
+        let mut regions = 0;
+        for &start in &cells {
+            if !seen.insert(start) {
+                continue;
+            }
+            regions += 1;
+            let mut queue = VecDeque::from([start]);
+            while let Some((x, y)) = queue.pop_front() {
+                for (x1, y1) in self.neighbors(x, y) {
+                    let number = self.cell(x1, y1);
+                    if number.state() != BoardCellState::Discovered || number.is_mine() {
+                        continue;
+                    }
+                    for next in self.neighbors(x1, y1) {
+                        if cells.contains(&next) && seen.insert(next) {
+                            queue.push_back(next);
+                        }
+                    }
+                }
+            }
+        }
+        regions
+    }
+
+    /// One labelled sample per frontier cell: what the player can see around
+    /// it, and whether it really is a mine. The label comes from the hidden
+    /// layout, so this is for building datasets offline, not for play.
+    pub fn training_samples(&self) -> Vec<(FrontierFeatures, bool)> {
+        let grid = self.to_numeric_grid();
+        self.frontier_cells()
+            .into_iter()
+            .map(|(x, y)| {
+                let (cx, cy) = (idx(x) as i32, idx(y) as i32);
+                let mut neighbors = [None; 8];
+                let offsets = iproduct!(-1..=1, -1..=1).filter(|&(dx, dy)| dx != 0 || dy != 0);
+                for (slot, (dx, dy)) in neighbors.iter_mut().zip(offsets) {
+                    let (x1, y1) = (cx + dx, cy + dy);
+                    if 0 <= x1 && x1 < self.rows as i32 && 0 <= y1 && y1 < self.cols as i32 {
+                        *slot = Some(grid[x1 as usize][y1 as usize]);
+                    }
+                }
+                let count = |code| neighbors.iter().filter(|&&n| n == Some(code)).count() as u8;
+                let features = FrontierFeatures {
+                    position: (x, y),
+                    neighbors,
+                    flagged_neighbors: count(NUMERIC_FLAGGED),
+                    covered_neighbors: count(NUMERIC_COVERED),
+                };
+                (features, self.cell(x, y).is_mine())
+            })
+            .collect()
+    }
+
+    fn is_frontier(&self, x: Coord, y: Coord) -> bool {
+        self.cell(x, y).is_covered()
+            && self.neighbors(x, y).any(|(x1, y1)| {
+                let cell = self.cell(x1, y1);
+                cell.state() == BoardCellState::Discovered && cell.value() > 0 && !cell.is_mine()
+            })
+    }
+
+    pub fn update(&mut self) {
+        self.tick_timer();
+        self.finalize();
+    }
+
+    pub fn tick_timer(&mut self) {
+        self.display_time = self.get_display_time();
+    }
+
+    /// Once the game is over, reveals the remaining cells: safe cells open,
+    /// mines are flagged on a win and shown on a loss. Does nothing while
+    /// the game is in progress, and calling it again changes nothing.
+    pub fn finalize(&mut self) {
+        if self.game_state != GameState::InProgress {
+            for x in 0..self.rows {
+                for y in 0..self.cols {
+                    if self.board[x][y].value() != 15 {
+                        self.board[x][y].click();
+                    } else if self.game_state == GameState::Won {
+                        self.board[x][y].cell = 15 + ((BoardCellState::Flagged as u8) << 4);
+                    } else if self.board[x][y].state() != BoardCellState::Exploded {
+                        self.board[x][y].cell = 15;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for Board {
+    fn default() -> Self {
         Self::new(9, 9, 10)
     }
 }
+
+/// Encoding used by `Board::to_numeric_grid`: revealed numbers are `0..=8`,
+/// everything else uses one of these negative codes.
+pub const NUMERIC_COVERED: i8 = -1;
+pub const NUMERIC_FLAGGED: i8 = -2;
+pub const NUMERIC_MINE: i8 = -3;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Offsets of `Board::neighbor_states`, clockwise from north.
+const COMPASS: [(i32, i32); 8] = [
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+];
+
+/// Largest number of unflagged frontier cells `Board::enumerate_arrangements`
+/// will search over.
+pub const MAX_ENUMERATED_FRONTIER: usize = 32;
+
+/// Backtracking state for `Board::enumerate_arrangements`: cells are decided
+/// in order, `need` is the number of mines each constraint still wants and
+/// `open` how many of its cells are still undecided. With `max` set, the
+/// first `max` arrangements are kept in `found`; without it the search runs
+/// to the end. Either way `tally[k]` counts the arrangements seen with `k`
+/// mines. When `outcomes` is non-empty, `outcomes[k][j]` also counts those
+/// where `j` of the `probe` cells are mines, or `j == 9` if `probe_cell` is.
+struct ArrangementSearch {
+    cell_constraints: Vec<Vec<usize>>,
+    need: Vec<i32>,
+    open: Vec<usize>,
+    mines: Vec<usize>,
+    bounds: (usize, usize),
+    max: Option<usize>,
+    found: Vec<Vec<usize>>,
+    tally: Vec<u64>,
+    probe_cell: Option<usize>,
+    probe: Vec<usize>,
+    outcomes: Vec<[u64; 10]>,
+}
+
+impl ArrangementSearch {
+    fn run(&mut self, cell: usize) {
+        if self.max.is_some_and(|max| self.found.len() >= max) || self.mines.len() > self.bounds.1 {
+            return;
+        }
+        if cell == self.cell_constraints.len() {
+            if self.mines.len() >= self.bounds.0 {
+                self.tally[self.mines.len()] += 1;
+                if !self.outcomes.is_empty() {
+                    let seen = match self.probe_cell {
+                        Some(probe) if self.mines.contains(&probe) => 9,
+                        _ => self.probe.iter().filter(|i| self.mines.contains(i)).count(),
+                    };
+                    self.outcomes[self.mines.len()][seen] += 1;
+                }
+                if self.max.is_some() {
+                    self.found.push(self.mines.clone());
+                }
+            }
+            return;
+        }
+        for mine in [true, false] {
+            let delta = mine as i32;
+            for &c in &self.cell_constraints[cell] {
+                self.need[c] -= delta;
+                self.open[c] -= 1;
+            }
+            let consistent = self.cell_constraints[cell]
+                .iter()
+                .all(|&c| 0 <= self.need[c] && self.need[c] as usize <= self.open[c]);
+            if consistent {
+                if mine {
+                    self.mines.push(cell);
+                }
+                self.run(cell + 1);
+                if mine {
+                    self.mines.pop();
+                }
+            }
+            for &c in &self.cell_constraints[cell] {
+                self.need[c] += delta;
+                self.open[c] += 1;
+            }
+        }
+    }
+}
+
+/// Root of `i` in the union-find `parent`, halving the path on the way.
+fn find_root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+/// Which totals `i + j` can be reached with `a[i]` and `b[j]` both true.
+fn or_convolve(a: &[bool], b: &[bool]) -> Vec<bool> {
+    let mut sums = vec![false; a.len() + b.len() - 1];
+    for (i, _) in a.iter().enumerate().filter(|&(_, &ok)| ok) {
+        for (j, _) in b.iter().enumerate().filter(|&(_, &ok)| ok) {
+            sums[i + j] = true;
+        }
+    }
+    sums
+}
+
+/// `ln(e^a + e^b)`, with `f64::NEG_INFINITY` standing for zero.
+fn ln_add(a: f64, b: f64) -> f64 {
+    let top = a.max(b);
+    if top == f64::NEG_INFINITY {
+        return top;
+    }
+    top + ((a - top).exp() + (b - top).exp()).ln()
+}
+
+/// Convolution of two sequences of counts given as natural logs.
+fn ln_convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut sums = vec![f64::NEG_INFINITY; a.len() + b.len() - 1];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate() {
+            sums[i + j] = ln_add(sums[i + j], x + y);
+        }
+    }
+    sums
+}
+
+/// Natural log of the binomial coefficient `n` choose `r`.
+fn ln_choose(n: usize, r: usize) -> f64 {
+    (1..=r.min(n - r))
+        .map(|i| ((n - i + 1) as f64 / i as f64).ln())
+        .sum()
+}
+
+/// Largest board `try_new` and `BoardBuilder` accept by default, to keep
+/// untrusted dimensions from exhausting memory.
+pub const MAX_CELLS: usize = 10_000_000;
+
+#[derive(Debug, Clone)]
+pub struct BoardBuilder {
+    rows: usize,
+    cols: usize,
+    mines: usize,
+    click_unflag: bool,
+    max_reveal_per_click: Option<usize>,
+    flood_mode: FloodMode,
+    adjacency: Adjacency,
+    mine_mobility: bool,
+    auto_complete: bool,
+    split_covered_view: bool,
+    clock: Arc<dyn Clock>,
+    max_cells: usize,
+}
+
+impl BoardBuilder {
+    pub fn new() -> Self {
+        Self {
+            rows: 9,
+            cols: 9,
+            mines: 10,
+            click_unflag: false,
+            max_reveal_per_click: None,
+            flood_mode: FloodMode::Full,
+            adjacency: Adjacency::Eight,
+            mine_mobility: false,
+            auto_complete: false,
+            split_covered_view: false,
+            clock: Arc::new(SystemClock),
+            max_cells: MAX_CELLS,
+        }
+    }
+
+    pub fn rows(mut self, rows: usize) -> Self {
+        self.rows = rows;
+        self
+    }
+
+    pub fn cols(mut self, cols: usize) -> Self {
+        self.cols = cols;
+        self
+    }
+
+    pub fn mines(mut self, mines: usize) -> Self {
+        self.mines = mines;
+        self
+    }
+
+    pub fn click_unflag(mut self, enabled: bool) -> Self {
+        self.click_unflag = enabled;
+        self
+    }
+
+    pub fn max_reveal_per_click(mut self, limit: Option<usize>) -> Self {
+        self.max_reveal_per_click = limit;
+        self
+    }
+
+    pub fn flood_mode(mut self, mode: FloodMode) -> Self {
+        self.flood_mode = mode;
+        self
+    }
+
+    pub fn adjacency(mut self, adjacency: Adjacency) -> Self {
+        self.adjacency = adjacency;
+        self
+    }
+
+    pub fn mine_mobility(mut self, enabled: bool) -> Self {
+        self.mine_mobility = enabled;
+        self
+    }
+
+    pub fn auto_complete(mut self, enabled: bool) -> Self {
+        self.auto_complete = enabled;
+        self
+    }
+
+    pub fn split_covered_view(mut self, enabled: bool) -> Self {
+        self.split_covered_view = enabled;
+        self
+    }
+
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn max_cells(mut self, max_cells: usize) -> Self {
+        self.max_cells = max_cells;
+        self
+    }
+
+    /// Validates the configuration: both dimensions must be non-zero, the
+    /// board must not exceed `max_cells` (overflowing `usize` counts as
+    /// exceeding it), every row and column must be addressable with
+    /// `Coord`, and the mines must fit outside the largest first-click safe
+    /// area.
+    pub fn build(self) -> Result<Board, MinesweeperError> {
+        if self.rows == 0 || self.cols == 0 {
+            return Err(MinesweeperError::InvalidDimensions {
+                rows: self.rows,
+                cols: self.cols,
+            });
+        }
+        let cells = self
+            .rows
+            .checked_mul(self.cols)
+            .filter(|&cells| cells <= self.max_cells)
+            .ok_or(MinesweeperError::TooLarge {
+                rows: self.rows,
+                cols: self.cols,
+                max_cells: self.max_cells,
+            })?;
+        if !fits_coord(self.rows) || !fits_coord(self.cols) {
+            return Err(MinesweeperError::InvalidDimensions {
+                rows: self.rows,
+                cols: self.cols,
+            });
+        }
+        let max = cells - self.rows.min(3) * self.cols.min(3);
+        if self.mines > max {
+            return Err(MinesweeperError::TooManyMines {
+                mines: self.mines,
+                max,
+            });
+        }
+        let mut board = Board::new(self.rows, self.cols, self.mines).with_clock(self.clock);
+        board.set_click_unflag(self.click_unflag);
+        board.set_max_reveal_per_click(self.max_reveal_per_click);
+        board.set_flood_mode(self.flood_mode);
+        board.set_adjacency(self.adjacency);
+        board.set_mine_mobility(self.mine_mobility);
+        board.set_auto_complete(self.auto_complete);
+        board.set_split_covered_view(self.split_covered_view);
+        Ok(board)
+    }
+}
+
+impl Default for BoardBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::render::AsciiRenderer;
+
+    fn board_from_layout(layout: &[&str]) -> Board {
+        let (rows, cols) = (layout.len(), layout[0].len());
+        let mines = layout.iter().map(|row| row.matches('*').count()).sum();
+        let mut board = Board::new(rows, cols, mines);
+        for (x, row) in layout.iter().enumerate() {
+            for (y, _) in row.chars().enumerate().filter(|&(_, c)| c == '*') {
+                board.board[x][y].cell = 15 + ((BoardCellState::Blank as u8) << 4);
+                for (x1, y1) in board.neighbors(x as Coord, y as Coord) {
+                    let cell = board.cell_mut(x1, y1);
+                    if cell.value() != 15 {
+                        cell.cell += 1;
+                    }
+                }
+            }
+        }
+        board.start = true;
+        board
+    }
+
+    #[test]
+    fn reveal_cross_skips_mines_in_its_arms() {
+        let mut board = board_from_layout(&["..*..", ".*...", "....."]);
+        let mut revealed = board.reveal_cross(1, 2, 2);
+        revealed.sort();
+        // (1, 4) is a zero, so the east arm cascades into the right corner.
+        assert_eq!(
+            revealed,
+            vec![
+                (0, 3),
+                (0, 4),
+                (1, 0),
+                (1, 2),
+                (1, 3),
+                (1, 4),
+                (2, 2),
+                (2, 3),
+                (2, 4)
+            ]
+        );
+        assert_eq!(board.game_state, GameState::InProgress);
+        assert_eq!(board.clicked_cells, 9);
+        assert_eq!(board.cell(0, 2).state(), BoardCellState::Blank);
+        assert_eq!(board.cell(1, 1).state(), BoardCellState::Blank);
+    }
+
+    #[test]
+    fn chord_completing_board_wins_once() {
+        let mut board = board_from_layout(&[".*.", "..."]);
+        board.click(1, 1);
+        board.flag(0, 1);
+        assert_eq!(board.game_state, GameState::InProgress);
+        board.click(1, 1);
+        assert_eq!(board.game_state, GameState::Won);
+        assert_eq!(board.clicked_cells, 5);
+        board.click(1, 1);
+        assert_eq!(board.game_state, GameState::Won);
+        assert_eq!(board.clicked_cells, 5);
+    }
+
+    #[test]
+    fn rotate90_moves_mines_clockwise() {
+        let board = board_from_layout(&["*..", "..."]);
+        let rotated = board.rotate90();
+        assert_eq!((rotated.rows, rotated.cols), (3, 2));
+        assert_eq!(rotated.solution().1, vec![(0, 1)]);
+        assert_eq!(rotated.adjacent_mine_count(1, 0), 1);
+        assert_eq!(rotated.get_board_cells()[1][0].value(), 1);
+    }
+
+    #[test]
+    fn zero_mine_board_is_won_on_first_click() {
+        let mut board = Board::new(4, 5, 0);
+        board.click(2, 3);
+        assert_eq!(board.game_state, GameState::Won);
+        assert_eq!(board.clicked_cells, 20);
+    }
+
+    #[test]
+    fn builder_validates_configuration() {
+        assert_eq!(
+            Board::builder().rows(0).build().unwrap_err(),
+            MinesweeperError::InvalidDimensions { rows: 0, cols: 9 }
+        );
+        assert_eq!(
+            Board::builder()
+                .rows(3)
+                .cols(3)
+                .mines(1)
+                .build()
+                .unwrap_err(),
+            MinesweeperError::TooManyMines { mines: 1, max: 0 }
+        );
+        assert_eq!(
+            Board::try_new(usize::MAX, 2, 0).unwrap_err(),
+            MinesweeperError::TooLarge {
+                rows: usize::MAX,
+                cols: 2,
+                max_cells: MAX_CELLS
+            }
+        );
+        assert!(Board::try_new(10_000, 1_001, 10).is_err());
+        let board = Board::builder().rows(4).cols(6).mines(15).build().unwrap();
+        assert_eq!((board.rows, board.cols, board.mines), (4, 6, 15));
+    }
+
+    #[cfg(feature = "u16-coords")]
+    #[test]
+    fn builder_rejects_dimensions_past_coord() {
+        assert!(Board::try_new(65_536, 1, 0).is_ok());
+        assert_eq!(
+            Board::try_new(65_537, 1, 0).unwrap_err(),
+            MinesweeperError::InvalidDimensions {
+                rows: 65_537,
+                cols: 1
+            }
+        );
+        assert!(Board::builder().rows(1).cols(70_000).build().is_err());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn set_cell_adjusts_the_counters() {
+        let mut board = Board::new(2, 2, 0);
+        board.set_cell(
+            0,
+            0,
+            BoardCell::from_raw_parts(1, BoardCellState::Discovered),
+        );
+        board.set_cell(0, 1, BoardCell::from_raw_parts(15, BoardCellState::Flagged));
+        assert!(board.start);
+        assert_eq!((board.clicked_cells, board.get_flagged_cells()), (1, 1));
+        board.set_cell(0, 0, BoardCell::from_raw_parts(1, BoardCellState::Blank));
+        board.set_cell(0, 1, BoardCell::from_raw_parts(15, BoardCellState::Blank));
+        assert_eq!((board.clicked_cells, board.get_flagged_cells()), (0, 0));
+        assert_eq!(board.cell(0, 0).state(), BoardCellState::Blank);
+
+        // Replacing an opened cell the counter never saw must not underflow.
+        board.board[1][1] = BoardCell::from_raw_parts(0, BoardCellState::Discovered);
+        board.set_cell(1, 1, BoardCell::from_raw_parts(0, BoardCellState::Blank));
+        assert_eq!(board.clicked_cells, 0);
+    }
+
+    #[test]
+    fn builder_applies_settings() {
+        let board = Board::builder()
+            .max_reveal_per_click(Some(5))
+            .flood_mode(FloodMode::SingleRing)
+            .adjacency(Adjacency::Orthogonal)
+            .build()
+            .unwrap();
+        assert_eq!(board.max_reveal_per_click, Some(5));
+        assert_eq!(board.flood_mode, FloodMode::SingleRing);
+        assert_eq!(board.neighbors(0, 0).count(), 2);
+
+        let clock = Arc::new(ManualClock::new());
+        let mut board = Board::builder()
+            .mine_mobility(true)
+            .auto_complete(true)
+            .split_covered_view(true)
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        assert!(board.mine_mobility && board.auto_complete && board.split_covered_view);
+        board.start_time = Some(clock.now());
+        clock.advance(Duration::from_secs(4));
+        assert_eq!(board.get_display_time(), Duration::from_secs(4));
+    }
+
+    #[test]
+    fn recount_flags_repairs_counter() {
+        let mut board = board_from_layout(&["*..", "..*"]);
+        board.flag(0, 0);
+        board.flag(1, 2);
+        board.board[0][1] = BoardCell::from_raw_parts(1, BoardCellState::Flagged);
+        board.flagged_cells = -3;
+        board.recount_flags();
+        assert_eq!(board.get_flagged_cells(), 3);
+    }
+
+    #[test]
+    fn restart_same_keeps_layout() {
+        let mut board = board_from_layout(&["*..", "...", "..*"]);
+        board.flag(1, 1);
+        board.click(0, 0);
+        assert_eq!(board.game_state, GameState::Lost);
+        let (_, mines) = board.solution();
+        board.restart_same();
+        assert_eq!(board.game_state, GameState::InProgress);
+        assert_eq!(board.solution().1, mines);
+        assert_eq!((board.clicked_cells, board.get_flagged_cells()), (0, 0));
+        assert!(board
+            .view()
+            .iter()
+            .flatten()
+            .all(|&cell| cell == CellView::Covered));
+        board.click(0, 2);
+        assert_eq!(board.game_state, GameState::InProgress);
+        assert_eq!(board.view()[0][2], CellView::Revealed(0));
+    }
+
+    #[test]
+    fn capped_reveal_resumes_with_continue_reveal() {
+        let mut board = board_from_layout(&["......"]);
+        board.set_max_reveal_per_click(Some(2));
+        board.click(0, 0);
+        assert_eq!(board.clicked_cells, 3);
+        assert_eq!(board.pending_reveals(), 1);
+        assert_eq!(board.continue_reveal(), vec![(0, 3), (0, 4)]);
+        assert_eq!(board.game_state, GameState::InProgress);
+        board.continue_reveal();
+        assert_eq!(board.game_state, GameState::Won);
+        assert!(board.continue_reveal().is_empty());
+    }
+
+    #[test]
+    fn numeric_grid_round_trips() {
+        let mut board = board_from_layout(&["*..", "..."]);
+        board.flag(0, 0);
+        board.click(1, 2);
+        let grid = board.to_numeric_grid();
+        assert_eq!(grid, vec![vec![-2, 1, 0], vec![-1, 1, 0]]);
+        assert_eq!(
+            Board::from_numeric_grid(&grid).unwrap().to_numeric_grid(),
+            grid
+        );
+        assert!(Board::from_numeric_grid(&[vec![0, 9]]).is_err());
+        assert!(Board::from_numeric_grid(&[vec![0, 1], vec![0]]).is_err());
+    }
+
+    #[test]
+    fn choose_positions_is_pinned_for_a_seed() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+        let mut rng = ChaCha8Rng::seed_from_u64(0x5EED);
+        assert_eq!(
+            choose_positions(&mut rng, 81, 10),
+            vec![72, 73, 80, 53, 21, 54, 55, 1, 4, 50]
+        );
+        let mut board = Board::new(9, 9, 10);
+        board.place_mines_with(&mut ChaCha8Rng::seed_from_u64(0x5EED), 4, 4, true);
+        let mines: Vec<Position> = iproduct!(0..9, 0..9)
+            .filter(|&(x, y)| board.cell(x, y).is_mine())
+            .collect();
+        assert_eq!(
+            mines,
+            vec![
+                (0, 2),
+                (3, 6),
+                (4, 7),
+                (5, 0),
+                (5, 7),
+                (6, 3),
+                (6, 4),
+                (7, 4),
+                (8, 2),
+                (8, 8)
+            ]
+        );
+    }
+
+    #[test]
+    fn reveals_reuse_the_visited_bitmap() {
+        let mut board = board_from_layout(&["...*", "....", "*..."]);
+        let buffer = board.reveal_visited.as_ptr();
+        board.click(0, 0);
+        let capacity = board.reveal_buffer_capacity();
+        board.click(2, 3);
+        assert!(capacity >= 12);
+        assert_eq!(board.reveal_buffer_capacity(), capacity);
+        assert_eq!(board.reveal_visited.as_ptr(), buffer);
+        assert!(board.reveal_visited.iter().all(|&word| word == 0));
+    }
+
+    #[test]
+    fn symmetries_of_mine_layout() {
+        let board = board_from_layout(&["*.*", "...", "*.*"]);
+        assert_eq!(board.symmetries(), Symmetry::ALL.to_vec());
+        let board = board_from_layout(&["*..", "...", "..*"]);
+        assert_eq!(
+            board.symmetries(),
+            vec![
+                Symmetry::Identity,
+                Symmetry::Rotate180,
+                Symmetry::Transpose,
+                Symmetry::AntiTranspose
+            ]
+        );
+        let board = board_from_layout(&["*..*"]);
+        assert_eq!(
+            board.symmetries(),
+            vec![
+                Symmetry::Identity,
+                Symmetry::Rotate180,
+                Symmetry::FlipHorizontal,
+                Symmetry::FlipVertical
+            ]
+        );
+    }
+
+    #[test]
+    fn training_samples_label_frontier_cells() {
+        let mut board = board_from_layout(&["..*", "..."]);
+        board.click(0, 0);
+        let samples = board.training_samples();
+        assert_eq!(samples.len(), 2);
+        let (features, is_mine) = &samples[0];
+        assert_eq!(features.position, (0, 2));
+        assert!(is_mine);
+        assert_eq!(
+            features.neighbors,
+            [None, None, None, Some(1), None, Some(1), Some(-1), None]
+        );
+        assert_eq!(
+            (features.flagged_neighbors, features.covered_neighbors),
+            (0, 1)
+        );
+        assert!(!samples[1].1);
+    }
+
+    #[test]
+    fn flag_cycle_leaves_no_counter_drift() {
+        let mut board = board_from_layout(&["*..", "..."]);
+        let start = board.get_flagged_cells();
+        board.flag(1, 2);
+        assert_eq!(board.get_flagged_cells(), start + 1);
+        board.flag(1, 2);
+        assert_eq!(board.cell(1, 2).state(), BoardCellState::Question);
+        assert_eq!(board.get_flagged_cells(), start);
+        board.flag(1, 2);
+        assert_eq!(board.cell(1, 2).state(), BoardCellState::Blank);
+        assert_eq!(board.get_flagged_cells(), start);
+
+        board.click(0, 0);
+        board.flag(0, 0);
+        assert_eq!(board.cell(0, 0).state(), BoardCellState::Exploded);
+        assert_eq!(board.get_flagged_cells(), start);
+    }
+
+    #[test]
+    fn enumerate_arrangements_on_small_frontier() {
+        let mut board = board_from_layout(&["..*", "..."]);
+        board.click(0, 0);
+        assert_eq!(
+            board.enumerate_arrangements(10),
+            Some(vec![vec![(0, 2)], vec![(1, 2)]])
+        );
+        assert_eq!(board.enumerate_arrangements(1), Some(vec![vec![(0, 2)]]));
+        board.flag(1, 2);
+        assert_eq!(board.enumerate_arrangements(10), Some(vec![vec![]]));
+    }
+
+    #[test]
+    fn enumerate_arrangements_keeps_frontier_cells_next_to_zeros_safe() {
+        let mut board = Board::from_numeric_grid(&[vec![-1, -1, -1], vec![1, 1, 0]]).unwrap();
+        board.mines = 1;
+        assert_eq!(board.enumerate_arrangements(10), Some(vec![vec![(0, 0)]]));
+    }
+
+    #[test]
+    fn enumerate_arrangements_keeps_mines_off_cells_next_to_zeros() {
+        let mut board = Board::from_numeric_grid(&[vec![-1, 1, -1, 1, -1, -1, 0, 0]]).unwrap();
+        board.mines = 2;
+        board.flag(0, 5);
+        board.flag(0, 5);
+        assert_eq!(board.cell(0, 5).state(), BoardCellState::Question);
+        assert_eq!(
+            board.enumerate_arrangements(10),
+            Some(vec![vec![(0, 0), (0, 4)]])
+        );
+    }
+
+    #[test]
+    fn expected_info_gain_is_entropy_of_the_outcome() {
+        let mut board = board_from_layout(&["..*", "..."]);
+        board.click(0, 0);
+        // (0, 2) is a mine or shows the 1 of its neighbour (1, 2), evenly.
+        assert!((board.expected_info_gain(0, 2) - 1.0).abs() < 1e-9);
+        assert_eq!(board.expected_info_gain(0, 0), 0.0);
+        board.flag(1, 2);
+        assert_eq!(board.expected_info_gain(0, 2), 0.0);
+    }
+
+    #[test]
+    fn expected_info_gain_searches_only_the_probed_group() {
+        // 17 independent groups of two cells, one mine each: 34 frontier
+        // cells in total, more than a whole-frontier search takes.
+        let unit = [0, -1, 1, 1, -1, 0];
+        let numbers: Vec<i8> = unit.iter().copied().cycle().take(6 * 17).collect();
+        let mut board = Board::from_numeric_grid(&[vec![-1; 6 * 17], numbers]).unwrap();
+        board.mines = 17;
+        assert!(board.search_cells().len() > MAX_ENUMERATED_FRONTIER);
+        assert!((board.expected_info_gain(0, 2) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn split_covered_view_marks_frontier() {
+        let mut board = board_from_layout(&["..*..", "....."]);
+        board.click(0, 0);
+        assert_eq!(board.view()[0][2], CellView::Covered);
+        board.set_split_covered_view(true);
+        let view = board.view();
+        assert_eq!(view[0][0], CellView::Revealed(0));
+        assert_eq!(view[0][2], CellView::CoveredFrontier);
+        assert_eq!(view[1][2], CellView::CoveredFrontier);
+        assert_eq!(view[0][3], CellView::CoveredInterior);
+        assert_eq!(view[1][4], CellView::CoveredInterior);
+    }
+
+    #[test]
+    fn region_count_splits_independent_frontiers() {
+        let mut board = board_from_layout(&["*.....*", "......."]);
+        board.click(0, 3);
+        assert_eq!(board.region_count(), 2);
+        board.flag(0, 0);
+        assert_eq!(board.region_count(), 2);
+    }
+
+    #[test]
+    fn region_mine_counts_bound_each_group() {
+        let mut board = board_from_layout(&["*.....*", "......."]);
+        board.click(0, 3);
+        assert_eq!(
+            board.region_mine_counts(),
+            vec![(vec![(0, 0), (1, 0)], 1..=1), (vec![(0, 6), (1, 6)], 1..=1)]
+        );
+        // The group is a single mine at (0, 2) or a pair at (0, 0) and
+        // (0, 4); the three cells on the right can take the other mine.
+        let mut board = Board::from_numeric_grid(&[vec![-1, 1, -1, 1, -1, -1, -1, -1]]).unwrap();
+        board.mines = 2;
+        assert_eq!(
+            board.region_mine_counts(),
+            vec![(vec![(0, 0), (0, 2), (0, 4)], 1..=2)]
+        );
+        board.mines = 5;
+        assert_eq!(
+            board.region_mine_counts(),
+            vec![(vec![(0, 0), (0, 2), (0, 4)], 2..=2)]
+        );
+    }
+
+    #[test]
+    fn entropy_counts_the_consistent_layouts() {
+        assert!((Board::new(3, 3, 1).entropy() - 9f64.log2()).abs() < 1e-9);
+        let mut board = board_from_layout(&["*.....*", "......."]);
+        board.click(0, 3);
+        // Each column of two holds one mine: four layouts.
+        assert!((board.entropy() - 2.0).abs() < 1e-9);
+        board.flag(0, 0);
+        assert!((board.entropy() - 1.0).abs() < 1e-9);
+        board.flag(0, 6);
+        assert_eq!(board.entropy(), 0.0);
+    }
+
+    #[test]
+    fn fifty_fifty_pairs_are_sealed_one_mine_pairs() {
+        let mut board = board_from_layout(&["*.....*", "......."]);
+        board.click(0, 3);
+        assert_eq!(
+            board.fifty_fifty_pairs(),
+            vec![((0, 0), (1, 0)), ((0, 6), (1, 6))]
+        );
+        let mut board = Board::from_numeric_grid(&[vec![-1, -1, -1], vec![1, 1, 1]]).unwrap();
+        board.mines = 1;
+        assert!(board.fifty_fifty_pairs().is_empty());
+        // The 1 at (0, 2) only touches (0, 1), which settles the pair.
+        let mut board = Board::from_numeric_grid(&[vec![-1, -1, 1], vec![1, 1, 1]]).unwrap();
+        board.mines = 1;
+        assert!(board.fifty_fifty_pairs().is_empty());
+    }
+
+    #[test]
+    fn neighbor_states_follow_compass_order() {
+        let mut board = board_from_layout(&["*..", "...", "..."]);
+        board.click(2, 2);
+        board.flag(0, 0);
+        assert_eq!(
+            board.neighbor_states(0, 1),
+            [
+                None,
+                None,
+                Some(CellView::Revealed(0)),
+                Some(CellView::Revealed(0)),
+                Some(CellView::Revealed(1)),
+                Some(CellView::Revealed(1)),
+                Some(CellView::Flagged),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn detect_patterns_reads_wall_patterns() {
+        let board = Board::from_numeric_grid(&[
+            vec![-1, -1, -1, -1, -1],
+            vec![1, 2, 1, 1, 0],
+            vec![0, 0, 0, 0, 0],
+        ])
+        .unwrap();
+        assert_eq!(
+            board.detect_patterns(),
+            vec![PatternMatch {
+                pattern: Pattern::OneTwoOne,
+                numbers: vec![(1, 0), (1, 1), (1, 2)],
+                mines: vec![(0, 0), (0, 2)],
+                safe: vec![(0, 1), (0, 3)],
+            }]
+        );
+
+        let board =
+            Board::from_numeric_grid(&[vec![0, 1, 2, 2, 1, 0], vec![-1, -1, -1, -1, -1, -1]])
+                .unwrap();
+        let found = board.detect_patterns();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].pattern, Pattern::OneTwoTwoOne);
+        assert_eq!(found[0].mines, vec![(1, 2), (1, 3)]);
+        assert_eq!(found[0].safe, vec![(1, 0), (1, 1), (1, 4), (1, 5)]);
+    }
+
+    #[test]
+    fn layout_hash_ignores_play_state() {
+        let mut board = board_from_layout(&["*..", "..."]);
+        let hash = board.layout_hash();
+        assert_eq!(hash, 0x90c6_91c9_adcb_819d);
+        board.click(1, 2);
+        board.flag(0, 0);
+        assert_eq!(board.layout_hash(), hash);
+        assert_ne!(board_from_layout(&[".*.", "..."]).layout_hash(), hash);
+        assert_ne!(board_from_layout(&["*.", "..", ".."]).layout_hash(), hash);
+    }
+
+    #[test]
+    fn flags_needed_counts_missing_flags() {
+        let mut board = board_from_layout(&["*.*", "..."]);
+        board.click(1, 1);
+        assert_eq!(board.flags_needed(1, 1), Some(2));
+        board.flag(0, 0);
+        assert_eq!(board.flags_needed(1, 1), Some(1));
+        board.flag(0, 1);
+        board.flag(0, 2);
+        assert_eq!(board.flags_needed(1, 1), Some(-1));
+        assert_eq!(board.flags_needed(0, 0), None);
+    }
+
+    #[test]
+    fn mobile_mines_skip_queued_cells() {
+        for _ in 0..100 {
+            let mut board = board_from_layout(&["*..........."]);
+            board.set_max_reveal_per_click(Some(1));
+            // Opens (0, 6) and (0, 5), queueing (0, 7) and (0, 4).
+            board.click(0, 6);
+            board.set_mine_mobility(true);
+            // Opens (0, 11) and the queued (0, 7), leaving (0, 4) pending
+            // and away from both.
+            board.click(0, 11);
+            assert_eq!(board.pending_reveals(), 3);
+            assert!(!board.cell(0, 4).is_mine());
+        }
+    }
+
+    #[test]
+    fn mobile_mines_keep_numbers_consistent() {
+        for _ in 0..20 {
+            let mut board = board_from_layout(&["*.....", "......", "......", ".....*"]);
+            let original = board_from_layout(&["*.....", "......", "......", ".....*"]);
+            board.set_mine_mobility(true);
+            board.click(1, 1);
+            assert!(!board.same_layout(&original));
+            assert!(!board.cell(0, 2).is_mine() && !board.cell(2, 0).is_mine());
+            let mines = board
+                .board
+                .iter()
+                .flatten()
+                .filter(|cell| cell.is_mine())
+                .count();
+            assert_eq!(mines, 2);
+            for (x, y) in iproduct!(0..4, 0..6) {
+                let cell = board.cell(x, y);
+                if cell.state() == BoardCellState::Discovered {
+                    assert!(!cell.is_mine());
+                }
+                if !cell.is_mine() {
+                    assert_eq!(cell.value(), board.adjacent_mine_count(x, y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn shared_clock_drives_timers() {
+        let clock = Arc::new(ManualClock::new());
+        let mut a = board_from_layout(&["*..", "..."]).with_clock(clock.clone());
+        let mut b = board_from_layout(&["..*", "..."]).with_clock(clock.clone());
+        a.start_time = Some(clock.now());
+        b.start_time = Some(clock.now());
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(a.get_display_time(), Duration::from_secs(5));
+        assert_eq!(b.get_display_time(), Duration::from_secs(5));
+        a.click(1, 2);
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(a.time_since_last_action(), Duration::from_secs(2));
+        assert_eq!(b.get_display_time(), Duration::from_secs(7));
+    }
+
+    #[test]
+    fn timer_builds_up_then_freezes_on_win() {
+        let clock = Arc::new(ManualClock::new());
+        let mut board = board_from_layout(&["*..", "..."]).with_clock(clock.clone());
+        board.start_time = Some(clock.now());
+        clock.advance(Duration::from_secs(3));
+        board.tick_timer();
+        assert_eq!(board.display_time, Duration::from_secs(3));
+        clock.advance(Duration::from_secs(4));
+        board.update();
+        assert_eq!(board.display_time, Duration::from_secs(7));
+        board.click(1, 2);
+        clock.advance(Duration::from_secs(2));
+        board.click(1, 0);
+        assert_eq!(board.game_state, GameState::Won);
+        clock.advance(Duration::from_secs(10));
+        board.update();
+        assert_eq!(board.get_display_time(), Duration::from_secs(9));
+    }
+
+    #[test]
+    fn timer_freezes_on_loss() {
+        let clock = Arc::new(ManualClock::new());
+        let mut board = board_from_layout(&["*..", "..."]).with_clock(clock.clone());
+        board.start_time = Some(clock.now());
+        clock.advance(Duration::from_secs(5));
+        board.click(0, 0);
+        assert_eq!(board.game_state, GameState::Lost);
+        clock.advance(Duration::from_secs(5));
+        board.tick_timer();
+        assert_eq!(board.get_display_time(), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn finalize_twice_changes_nothing() {
+        // Raw cells, as `BoardCell`'s `PartialEq` never matches exploded cells.
+        let raw = |board: &Board| {
+            board
+                .board
+                .iter()
+                .flatten()
+                .map(|c| c.cell)
+                .collect::<Vec<_>>()
+        };
+        let mut won = board_from_layout(&["*..", "..."]);
+        won.click(1, 2);
+        won.click(1, 0);
+        won.finalize();
+        let once = raw(&won);
+        won.finalize();
+        assert_eq!(raw(&won), once);
+        assert_eq!(won.cell(0, 0).state(), BoardCellState::Flagged);
+
+        let mut lost = board_from_layout(&["*.*", "..."]);
+        lost.click(1, 0);
+        lost.click(0, 2);
+        lost.finalize();
+        let once = raw(&lost);
+        lost.finalize();
+        assert_eq!(raw(&lost), once);
+        assert_eq!(lost.cell(0, 0).state(), BoardCellState::Discovered);
+        assert!(lost.cell(0, 0).is_mine());
+        assert_eq!(lost.cell(0, 2).state(), BoardCellState::Exploded);
+        assert!(lost
+            .board
+            .iter()
+            .flatten()
+            .filter(|c| !c.is_mine())
+            .all(|c| c.state() == BoardCellState::Discovered));
+    }
+
+    #[test]
+    fn openness_is_area_over_perimeter() {
+        let mut board = board_from_layout(&["....*", "....."]);
+        assert_eq!(board.openness(), 0.0);
+        board.click(0, 0);
+        // Columns 0-3 open, bordering (0, 4) and (1, 4).
+        assert_eq!(board.openness(), 8.0 / 2.0);
+    }
+
+    #[test]
+    fn play_state_round_trips() {
+        let mut board = board_from_layout(&["*...", "...."]);
+        board.click(1, 3);
+        board.flag(0, 0);
+        let saved = board.save_play_state();
+        let (view, order) = (board.view(), board.reveal_order.clone());
+        board.click(1, 0);
+        board.click(0, 1);
+        assert_eq!(board.game_state, GameState::Won);
+        board.restore_play_state(saved.clone());
+        assert_eq!(board.view(), view);
+        assert_eq!(board.reveal_order, order);
+        assert_eq!(board.game_state, GameState::InProgress);
+        assert_eq!(board.get_flagged_cells(), 1);
+        board.restore_play_state(saved);
+        board.click(0, 1);
+        board.click(1, 0);
+        assert_eq!(board.game_state, GameState::Won);
+    }
+
+    #[test]
+    fn auto_complete_opens_satisfied_numbers() {
+        let mut board = board_from_layout(&["*..", "...", "..*"]);
+        board.set_auto_complete(true);
+        board.click(1, 1);
+        board.flag(0, 0);
+        assert_eq!(board.game_state, GameState::InProgress);
+        board.flag(2, 2);
+        assert_eq!(board.game_state, GameState::Won);
+    }
+
+    #[test]
+    fn resolve_satisfied_wins_or_loses_by_the_flags() {
+        let mut board = board_from_layout(&["*.."]);
+        board.click(0, 1);
+        board.flag(0, 0);
+        assert_eq!(board.resolve_satisfied(), 1);
+        assert_eq!(board.game_state, GameState::Won);
+
+        let mut board = board_from_layout(&["..*"]);
+        board.click(0, 1);
+        board.flag(0, 0);
+        assert_eq!(board.resolve_satisfied(), 0);
+        assert_eq!(board.game_state, GameState::Lost);
+        assert_eq!(board.cell(0, 2).state(), BoardCellState::Exploded);
+    }
+
+    #[test]
+    fn take_transition_reports_the_end_once() {
+        let mut board = board_from_layout(&["*..*"]);
+        board.click(0, 1);
+        assert_eq!(board.take_transition(), None);
+        board.click(0, 0);
+        assert_eq!(board.take_transition(), Some(EndReason::Lost));
+        assert_eq!(board.take_transition(), None);
+        board.restart_same();
+        board.click(0, 1);
+        board.click(0, 2);
+        assert_eq!(board.game_state, GameState::Won);
+        assert_eq!(board.take_transition(), Some(EndReason::Won));
+        assert_eq!(board.take_transition(), None);
+    }
+
+    #[test]
+    fn click_unflag_clears_flags_instead_of_ignoring_them() {
+        let mut board = board_from_layout(&["*..*"]);
+        board.flag(0, 1);
+        board.click(0, 1);
+        assert_eq!(board.cell(0, 1).state(), BoardCellState::Flagged);
+        board.set_click_unflag(true);
+        board.click(0, 1);
+        assert_eq!(board.cell(0, 1).state(), BoardCellState::Blank);
+        assert_eq!(board.get_flagged_cells(), 0);
+        board.click(0, 1);
+        assert_eq!(board.cell(0, 1).state(), BoardCellState::Discovered);
+        assert!(
+            Board::builder()
+                .click_unflag(true)
+                .build()
+                .unwrap()
+                .click_unflag
+        );
+    }
+
+    #[test]
+    fn is_legal_matches_what_the_move_would_do() {
+        let mut board = board_from_layout(&["*..*"]);
+        board.click(0, 1);
+        assert!(board.is_legal(MoveKind::Click, 0, 2));
+        assert!(board.is_legal(MoveKind::Flag, 0, 0));
+        assert!(!board.is_legal(MoveKind::Flag, 0, 1));
+        assert!(!board.is_legal(MoveKind::Click, 0, 4));
+        // The 1 at (0, 1) only chords once it has its flag.
+        assert!(!board.is_legal(MoveKind::Click, 0, 1));
+        board.flag(0, 0);
+        assert!(board.is_legal(MoveKind::Click, 0, 1));
+        assert!(!board.is_legal(MoveKind::Click, 0, 0));
+        board.set_click_unflag(true);
+        assert!(board.is_legal(MoveKind::Click, 0, 0));
+        board.click(0, 3);
+        assert!(!board.is_legal(MoveKind::Click, 0, 2));
+    }
+
+    #[test]
+    fn activate_returns_the_resulting_state() {
+        let mut board = board_from_layout(&["*..*"]);
+        assert_eq!(board.activate(0, 1), GameState::InProgress);
+        board.flag(0, 0);
+        board.flag(0, 3);
+        board.flag(0, 3);
+        // Questioned cells are ignored, chording the 1 at (0, 1) opens (0, 2).
+        assert_eq!(board.activate(0, 3), GameState::InProgress);
+        assert_eq!(board.cell(0, 3).state(), BoardCellState::Question);
+        assert_eq!(board.activate(0, 1), GameState::Won);
+    }
+
+    #[test]
+    fn debug_reveal_all_safe_wins_and_records_the_reveals() {
+        let mut board = board_from_layout(&["*...", "...*"]);
+        board.click(0, 1);
+        board.flag(1, 0);
+        board.debug_reveal_all_safe();
+        assert_eq!(board.game_state, GameState::Won);
+        assert_eq!(board.clicked_cells, 6);
+        assert_eq!(board.cell(1, 0).state(), BoardCellState::Discovered);
+        assert_eq!(board.reveal_order[0][1], Some(0));
+        assert_eq!(board.reveal_order[0][2], Some(1));
+        assert_eq!(board.reveal_order[1][2], Some(5));
+        assert_eq!(board.reveal_order[0][0], None);
+    }
+
+    #[test]
+    fn flood_modes_bound_the_cascade() {
+        let layout = ["....*", "....."];
+        let opened = |mode| {
+            let mut board = board_from_layout(&layout);
+            board.set_flood_mode(mode);
+            board.click(0, 0);
+            board.clicked_cells
+        };
+        assert_eq!(opened(FloodMode::Full), 8);
+        assert_eq!(opened(FloodMode::SingleRing), 4);
+        assert_eq!(opened(FloodMode::None), 1);
+
+        for mode in [FloodMode::Full, FloodMode::SingleRing, FloodMode::None] {
+            let mut board = board_from_layout(&layout);
+            board.set_flood_mode(mode);
+            for (x, y) in iproduct!(0..2, 0..5) {
+                if !board.cell(x, y).is_mine() {
+                    board.click(x, y);
+                }
+            }
+            assert_eq!(board.game_state, GameState::Won);
+        }
+    }
+
+    #[test]
+    fn claimed_flags_feasible_checks_total_and_numbers() {
+        let mut board = board_from_layout(&["*..", "...", "..*"]);
+        board.click(1, 1);
+        board.click(1, 0);
+        board.flag(0, 0);
+        assert!(board.flags_consistent());
+        assert!(!board.claimed_flags_feasible());
+        board.flag(2, 1);
+        assert!(!board.claimed_flags_feasible());
+        board.flag(2, 1);
+        board.flag(2, 1);
+        board.flag(2, 2);
+        assert!(board.claimed_flags_feasible());
+    }
+
+    #[test]
+    fn revealed_in_order_follows_the_cascade() {
+        let mut board = board_from_layout(&["...*", "...."]);
+        board.click(0, 0);
+        assert_eq!(
+            board.revealed_in_order(),
+            vec![(0, 0), (0, 1), (1, 0), (1, 1), (0, 2), (1, 2)]
+        );
+        board.click(0, 3);
+        assert_eq!(board.revealed_in_order().last(), Some(&(0, 3)));
+    }
+
+    #[test]
+    fn adjacency_changes_the_counts() {
+        let counts = |adjacency| {
+            let mut board = Board::new(5, 5, 16);
+            board.set_adjacency(adjacency);
+            board.click(2, 2);
+            (board.cell(1, 2).value(), board.cell(1, 1).value())
+        };
+        assert_eq!(counts(Adjacency::Eight), (3, 5));
+        assert_eq!(counts(Adjacency::Orthogonal), (1, 2));
+        assert_eq!(counts(Adjacency::Diagonal), (2, 3));
+    }
+
+    #[test]
+    fn share_text_summarises_the_game() {
+        let mut board = board_from_layout(&["*..", "...", "..*"]);
+        assert_eq!(board.three_bv(), 2);
+        board.click(0, 1);
+        board.click(0, 0);
+        assert_eq!(
+            board.share_text(),
+            "Minesweeper 3x3 (2 mines)\nLost in 0.0s | 3BV 2\n💥🟩⬜\n⬜⬜⬜\n⬜⬜💣\n"
+        );
+    }
+
+    #[test]
+    fn share_text_hides_mines_while_in_progress() {
+        let clock = Arc::new(ManualClock::new());
+        let mut board = board_from_layout(&["*..", "...", "..*"]).with_clock(clock.clone());
+        board.start_time = Some(clock.now());
+        board.click(0, 1);
+        clock.advance(Duration::from_millis(2500));
+        assert_eq!(
+            board.share_text(),
+            "Minesweeper 3x3 (2 mines)\nIn progress in 2.5s | 3BV 2\n⬜🟩⬜\n⬜⬜⬜\n⬜⬜⬜\n"
+        );
+    }
+
+    #[test]
+    fn ascii_renderer_draws_the_board() {
+        let mut board = board_from_layout(&["*..", "...", "..*"]);
+        board.click(0, 2);
+        board.flag(0, 0);
+        board.flag(2, 0);
+        board.flag(2, 0);
+        board.click(2, 2);
+        let text: Vec<String> = board
+            .render_with(&AsciiRenderer)
+            .into_iter()
+            .map(|row| row.into_iter().collect())
+            .collect();
+        assert_eq!(text.join("\n"), "F1.\n#21\n?#X");
+    }
+
+    #[test]
+    fn value_histogram_counts_revealed_numbers() {
+        let mut board = board_from_layout(&["*..", "...", "..*"]);
+        assert_eq!(board.value_histogram(), [0; 9]);
+        board.click(0, 2);
+        // (0, 2) cascades into (0, 1), (1, 1) and (1, 2); mines never count.
+        assert_eq!(board.value_histogram(), [1, 2, 1, 0, 0, 0, 0, 0, 0]);
+        board.click(0, 0);
+        assert_eq!(board.value_histogram(), [1, 2, 1, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn mines_in_region_counts_inside_and_clips_at_the_edge() {
+        let board = board_from_layout(&["*...", "..*.", "...*"]);
+        assert_eq!(board.mines_in_region(0, 0, 1, 2), 2);
+        assert_eq!(board.mines_in_region(1, 1, 1, 1), 0);
+        assert_eq!(board.mines_in_region(1, 2, 10, 10), 2);
+        assert_eq!(board.mines_in_region(2, 0, 1, 3), 0);
+    }
+
+    #[test]
+    fn row_and_column_return_their_cells() {
+        let mut board = board_from_layout(&["*..", "..*"]);
+        board.click(1, 0);
+        board.flag(0, 0);
+        let row: Vec<_> = board
+            .row(1)
+            .iter()
+            .map(|c| (c.value(), c.state()))
+            .collect();
+        assert_eq!(
+            row,
+            [
+                (1, BoardCellState::Discovered),
+                (2, BoardCellState::Blank),
+                (15, BoardCellState::Blank)
+            ]
+        );
+        let column: Vec<_> = board
+            .column(0)
+            .iter()
+            .map(|c| (c.value(), c.state()))
+            .collect();
+        assert_eq!(
+            column,
+            [
+                (15, BoardCellState::Flagged),
+                (1, BoardCellState::Discovered)
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn row_out_of_range_panics() {
+        board_from_layout(&["*..", "..*"]).row(2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn column_out_of_range_panics() {
+        board_from_layout(&["*..", "..*"]).column(3);
+    }
+
+    #[test]
+    fn unconstrained_cells_have_no_revealed_neighbours() {
+        let mut board = board_from_layout(&["..*...", "......"]);
+        board.click(0, 0);
+        board.flag(1, 5);
+        assert_eq!(
+            board.unconstrained_cells(),
+            vec![(0, 3), (0, 4), (0, 5), (1, 3), (1, 4)]
+        );
+    }
+
+    #[test]
+    fn unconstrained_probability_apportions_mines() {
+        // The 1s at (0, 1) and (1, 1) share one mine between (0, 2) and
+        // (1, 2), leaving the other mine somewhere in the six cells past it.
+        let mut board = board_from_layout(&["..*...", ".....*"]);
+        board.click(0, 0);
+        assert_eq!(board.unconstrained_cells().len(), 6);
+        assert_eq!(board.unconstrained_mine_probability(), 1.0 / 6.0);
+        board.flag(1, 5);
+        assert_eq!(board.unconstrained_mine_probability(), 0.0);
+    }
+
+    #[test]
+    fn analyze_collects_metrics() {
+        let mut board = board_from_layout(&["*.....*", "......."]);
+        board.click(0, 3);
+        assert_eq!(
+            board.analyze(),
+            BoardAnalysis {
+                three_bv: 3,
+                region_count: 2
+            }
+        );
+        for _ in 0..20 {
+            let mut board = Board::new(12, 16, 40);
+            board.start_at_origin();
+            board.click(6, 8);
+            board.flag(11, 15);
+            assert_eq!(
+                board.analyze(),
+                BoardAnalysis {
+                    three_bv: board.three_bv(),
+                    region_count: board.region_count()
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn action_log_applies_moves_in_order() {
+        let mut board = board_from_layout(&["*..", "..."]);
+        assert_eq!(board.apply_action_log("C 1 2\nF 0 0\n\n"), Ok(()));
+        assert_eq!(board.cell(0, 0).state(), BoardCellState::Flagged);
+        assert_eq!(
+            board.apply_action_log("F 1 0\nC 5 0"),
+            Err(MinesweeperError::OutOfBounds { x: 5, y: 0 })
+        );
+        assert!(matches!(
+            board.apply_action_log("X 1 1"),
+            Err(MinesweeperError::ParseError(_))
+        ));
+        assert!(matches!(
+            board.apply_action_log("C 1"),
+            Err(MinesweeperError::ParseError(_))
+        ));
+        assert_eq!(board.apply_action_log("F 1 0\nF 1 0\nC 1 0\nC 9 9"), Ok(()));
+        assert_eq!(board.game_state, GameState::Won);
+    }
+
+    #[test]
+    fn opening_qualities_use_zero_regions() {
+        let board = board_from_layout(&["*...", "....", "...*"]);
+        assert_eq!(
+            board.opening_qualities(),
+            vec![vec![0, 1, 6, 6], vec![1, 1, 1, 1], vec![6, 6, 1, 0]]
+        );
+    }
+
+    #[test]
+    fn start_in_region_keeps_mines_inside() {
+        let region: Vec<Position> = (0..6).map(|y| (3, y)).collect();
+        let mut board = Board::new(4, 6, 5);
+        assert_eq!(
+            board.start_in_region(3, 0, &region),
+            Err(MinesweeperError::TooManyMines { mines: 5, max: 4 })
+        );
+        assert_eq!(
+            board.start_in_region(0, 0, &[(4, 0)]),
+            Err(MinesweeperError::OutOfBounds { x: 4, y: 0 })
+        );
+        assert_eq!(
+            board.start_in_region(0, 6, &region),
+            Err(MinesweeperError::OutOfBounds { x: 0, y: 6 })
+        );
+        assert_eq!(board.start_in_region(0, 0, &region), Ok(()));
+        for (x, y) in iproduct!(0..4, 0..6) {
+            let cell = board.cell(x, y);
+            if cell.is_mine() {
+                assert_eq!(x, 3);
+            } else {
+                assert_eq!(cell.value(), board.adjacent_mine_count(x, y));
+            }
+        }
+        assert_eq!(
+            board
+                .board
+                .iter()
+                .flatten()
+                .filter(|cell| cell.is_mine())
+                .count(),
+            5
+        );
+        board.click(0, 0);
+        assert_eq!(board.game_state, GameState::InProgress);
+    }
+
+    #[test]
+    fn reveal_waves_count_cells_per_distance() {
+        let mut board = board_from_layout(&["....*", "....."]);
+        board.click(0, 0);
+        assert_eq!(board.last_reveal_waves(), &[1, 3, 2, 2]);
+        board.click(1, 4);
+        assert_eq!(board.last_reveal_waves(), &[1]);
+        board.restart_same();
+        assert!(board.last_reveal_waves().is_empty());
+    }
+
+    #[test]
+    fn would_win_if_flags_correct_peeks_at_mines() {
+        let mut board = board_from_layout(&["*..", "..*"]);
+        board.click(1, 0);
+        assert!(!board.would_win_if_flags_correct());
+        board.flag(0, 0);
+        board.flag(0, 1);
+        assert!(!board.would_win_if_flags_correct());
+        board.flag(0, 1);
+        board.flag(0, 1);
+        board.flag(1, 2);
+        assert!(board.would_win_if_flags_correct());
+    }
+
+    #[test]
+    fn subboard_recounts_inside_the_rectangle() {
+        let mut board = board_from_layout(&["*...", "....", "...*"]);
+        board.click(0, 3);
+        board.flag(0, 0);
+        let sub = board.subboard(0, 1, 2, 3).unwrap();
+        assert_eq!((sub.rows, sub.cols, sub.mines), (3, 3, 1));
+        assert_eq!(sub.get_flagged_cells(), 0);
+        assert_eq!(sub.cell(0, 0).state(), BoardCellState::Discovered);
+        assert_eq!(sub.cell(0, 0).value(), 0);
+        assert_eq!(sub.cell(1, 1).value(), 1);
+        let corner = board.subboard(0, 0, 0, 0).unwrap();
+        assert_eq!(corner.get_flagged_cells(), 1);
+        assert_eq!(
+            board.subboard(0, 0, 3, 0).unwrap_err(),
+            MinesweeperError::OutOfBounds { x: 3, y: 0 }
+        );
+        assert!(board.subboard(2, 0, 1, 0).is_err());
+    }
+
+    #[test]
+    fn flag_efficiency_counts_chorded_flags() {
+        let mut board = board_from_layout(&["*..*", "....", "...."]);
+        board.click(2, 0);
+        assert_eq!(board.flag_efficiency(), 1.0);
+        board.flag(0, 0);
+        board.flag(0, 3);
+        assert_eq!(board.flag_efficiency(), 0.0);
+        board.click(1, 0);
+        assert_eq!(board.flag_efficiency(), 0.5);
+    }
+
+    #[test]
+    fn flawless_flagging_is_decided_at_the_win() {
+        let mut board = board_from_layout(&["*..", "..."]);
+        board.click(1, 2);
+        board.click(1, 0);
+        assert_eq!(board.game_state, GameState::Won);
+        assert!(!board.was_flawless_flagging());
+        board.update();
+        assert_eq!(board.cell(0, 0).state(), BoardCellState::Flagged);
+        board.click(0, 1);
+        assert!(!board.was_flawless_flagging());
+    }
+
+    #[test]
+    fn click_opens_its_cell_while_a_reveal_is_pending() {
+        let mut board = board_from_layout(&[".........."]);
+        board.set_max_reveal_per_click(Some(1));
+        board.click(0, 0);
+        board.click(0, 9);
+        assert_eq!(board.cell(0, 9).state(), BoardCellState::Discovered);
+        assert_eq!(board.pending_reveals(), 2);
+
+        let mut board = board_from_layout(&[".........*"]);
+        board.set_max_reveal_per_click(Some(1));
+        board.click(0, 0);
+        board.click(0, 9);
+        assert_eq!(board.game_state, GameState::Lost);
+        assert_eq!(board.pending_reveals(), 0);
+    }
+
+    #[test]
+    fn flag_protects_cell_queued_by_capped_reveal() {
+        let mut board = board_from_layout(&["..*", "...", "..."]);
+        board.set_max_reveal_per_click(Some(1));
+        board.click(1, 1);
+        board.flag(0, 0);
+        board.click(1, 1);
+        assert_eq!(board.view()[0][1], CellView::Revealed(1));
+        board.flag(0, 2);
+        board.continue_reveal();
+        assert_eq!(board.game_state, GameState::InProgress);
+        assert_eq!(board.cell(0, 2).state(), BoardCellState::Flagged);
+    }
+}
@@ -0,0 +1,54 @@
+use std::{error, fmt};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MinesweeperError {
+    OutOfBounds {
+        x: usize,
+        y: usize,
+    },
+    InvalidDimensions {
+        rows: usize,
+        cols: usize,
+    },
+    TooManyMines {
+        mines: usize,
+        max: usize,
+    },
+    TooLarge {
+        rows: usize,
+        cols: usize,
+        max_cells: usize,
+    },
+    ParseError(String),
+    Unsolvable,
+}
+
+impl fmt::Display for MinesweeperError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MinesweeperError::OutOfBounds { x, y } => {
+                write!(f, "cell ({x}, {y}) is outside the board")
+            }
+            MinesweeperError::InvalidDimensions { rows, cols } => {
+                write!(f, "invalid board dimensions {rows}x{cols}")
+            }
+            MinesweeperError::TooManyMines { mines, max } => {
+                write!(f, "{mines} mines requested but at most {max} fit")
+            }
+            MinesweeperError::TooLarge {
+                rows,
+                cols,
+                max_cells,
+            } => {
+                write!(
+                    f,
+                    "a {rows}x{cols} board exceeds the limit of {max_cells} cells"
+                )
+            }
+            MinesweeperError::ParseError(reason) => write!(f, "parse error: {reason}"),
+            MinesweeperError::Unsolvable => write!(f, "board cannot be solved without guessing"),
+        }
+    }
+}
+
+impl error::Error for MinesweeperError {}
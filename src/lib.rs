@@ -1,4 +1,7 @@
 pub mod board;
+pub mod clock;
+pub mod error;
+pub mod render;
 pub mod solver;
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,28 @@
+use crate::board::{BoardCell, BoardCellState};
+
+pub trait CellRenderer {
+    type Output;
+    fn render_cell(&self, cell: &BoardCell) -> Self::Output;
+}
+
+/// Renders cells as single characters: `#` covered, `F` flagged, `?`
+/// question, `.` an empty discovered cell, `1`-`8` numbers, `*` a revealed
+/// mine and `X` the exploded one.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AsciiRenderer;
+
+impl CellRenderer for AsciiRenderer {
+    type Output = char;
+    fn render_cell(&self, cell: &BoardCell) -> char {
+        match cell.state() {
+            BoardCellState::Discovered if cell.is_mine() => '*',
+            BoardCellState::Discovered if cell.value() == 0 => '.',
+            BoardCellState::Discovered => char::from(b'0' + cell.value()),
+            BoardCellState::Blank => '#',
+            BoardCellState::Flagged => 'F',
+            BoardCellState::Question => '?',
+            BoardCellState::Exploded => 'X',
+            BoardCellState::Other => ' ',
+        }
+    }
+}
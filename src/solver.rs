@@ -1,16 +1,15 @@
 use crate::board::{BoardCell, BoardCellState, BoardCells};
 use arrayvec::ArrayVec;
-use itertools::zip;
 use lazy_static::lazy_static;
 use nalgebra::{ArrayStorage, Matrix, Matrix2};
 use std::{
     collections::HashSet,
+    iter::zip,
     sync::{
         atomic::{AtomicUsize, Ordering},
         mpsc::channel,
         Arc, RwLock,
     },
-    thread::{self, JoinHandle},
 };
 
 
@@ -78,7 +77,7 @@ impl Solver {
         });
     }
 
-    pub fn update(&mut self, board: &BoardCells) {}
+    pub fn update(&mut self, _board: &BoardCells) {}
 
     pub fn get_cell_state(&self, x: usize, y: usize) -> SolvedCellState {
         let board = self.board.read().unwrap();
@@ -125,10 +124,10 @@ impl SolvableBoard {
         }
     }
 
+    #[allow(dead_code)]
     pub fn update(&mut self, board: &BoardCells) {
         self.board = board.clone();
-        self.verified
-            .extend(SolvableBoard::aux_get_verified(board).into_iter());
+        self.verified.extend(SolvableBoard::aux_get_verified(board));
     }
 
     pub fn extend(&mut self, iter: impl Iterator<Item = Answer>) {
@@ -146,6 +145,7 @@ impl SolvableBoard {
     }
 }
 
+#[allow(dead_code)]
 #[derive(PartialEq, Eq)]
 enum Answer {
     Mine(usize, usize),